@@ -0,0 +1,516 @@
+use rodio::buffer::SamplesBuffer;
+use rodio::source::{Buffered, ChannelVolume};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Fracción del volumen de la música mientras suena un efecto importante
+const DUCKED_MUSIC: f32 = 0.2;
+
+// Atenuación extra de una fuente con una pared de por medio
+const OCCLUDED_GAIN: f32 = 0.3;
+
+// Cuánto puede cambiar la intensidad de la música por segundo
+const INTENSITY_FADE_PER_SEC: f32 = 0.6;
+
+// Frecuencia de muestreo de los sonidos sintetizados
+const SYNTH_RATE: u32 = 22050;
+
+// Duración (segundos) de un ciclo de la respiración del monstruo
+const BREATH_CYCLE: f32 = 3.2;
+
+// Duración (segundos) del bucle de la capa de tensión y sus parciales
+// (frecuencia, amplitud). Cada frecuencia da un número entero de ciclos en el
+// bucle para que se repita sin saltos; las dos primeras baten entre sí.
+const DRONE_LENGTH: u32 = 8;
+const DRONE_PARTIALS: [(f32, f32); 5] = [
+    (55.0, 0.5),
+    (55.25, 0.4),
+    (82.5, 0.25),
+    (110.125, 0.15),
+    (146.875, 0.1),
+];
+
+/// Sonidos que conoce el juego.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Music,
+    /// Zumbido grave que reemplaza a la música cuando el monstruo se acerca.
+    Tension,
+    Footsteps,
+    /// Respiración del monstruo, que suena junto con sus pasos.
+    Breathing,
+    Won,
+    GameOver,
+}
+
+impl Sound {
+    const ALL: [Sound; 6] = [
+        Sound::Music,
+        Sound::Tension,
+        Sound::Footsteps,
+        Sound::Breathing,
+        Sound::Won,
+        Sound::GameOver,
+    ];
+
+    // Archivo del sonido en la carpeta de assets; `None` si se sintetiza
+    fn file_name(self) -> Option<&'static str> {
+        match self {
+            Sound::Music => Some("horror.mp3"),
+            Sound::Tension => None,
+            Sound::Footsteps => Some("footsteps.mp3"),
+            Sound::Breathing => None,
+            Sound::Won => Some("change.mp3"),
+            Sound::GameOver => Some("game-over.mp3"),
+        }
+    }
+
+    // Amplificación propia de cada sonido para equilibrar la mezcla
+    fn gain(self) -> f32 {
+        match self {
+            Sound::Music => 0.3,
+            Sound::Tension => 0.5,
+            Sound::Footsteps => 0.8,
+            Sound::Breathing => 0.9,
+            Sound::Won | Sound::GameOver => 1.0,
+        }
+    }
+}
+
+/// Panorámica y volumen de una fuente de sonido vista desde el jugador.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Positional {
+    /// -1.0 completamente a la izquierda, 1.0 completamente a la derecha.
+    pub pan: f32,
+    /// Volumen entre 0.0 y 1.0 tras la distancia y la oclusión.
+    pub gain: f32,
+}
+
+impl Positional {
+    /// Calcula la panorámica según el ángulo relativo al jugador y atenúa con la
+    /// distancia (en celdas) y si hay una pared entre ambos.
+    pub fn new(
+        listener: (f32, f32),
+        listener_angle: f32,
+        emitter: (f32, f32),
+        occluded: bool,
+    ) -> Self {
+        let dx = emitter.0 - listener.0;
+        let dy = emitter.1 - listener.1;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        let mut relative = (dy.atan2(dx) - listener_angle).rem_euclid(2.0 * PI);
+        if relative > PI {
+            relative -= 2.0 * PI;
+        }
+
+        let mut gain = 1.0 / (1.0 + 0.5 * distance * distance);
+        if occluded {
+            gain *= OCCLUDED_GAIN;
+        }
+
+        Positional {
+            pan: relative.sin(),
+            gain,
+        }
+    }
+
+    // Ganancias izquierda/derecha de igual potencia
+    fn channel_gains(&self) -> [f32; 2] {
+        let angle = (self.pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        [angle.cos() * self.gain, angle.sin() * self.gain]
+    }
+}
+
+// Un bucle cuyo volumen por canal se actualiza mientras suena
+struct PositionalLoop {
+    sink: Sink,
+    gains: Arc<Mutex<[f32; 2]>>,
+}
+
+// Un sonido decodificado (o sintetizado) una sola vez; los clones comparten
+// las muestras.
+type Clip = Buffered<Box<dyn Source<Item = i16> + Send>>;
+
+/// Punto único de reproducción de audio del juego.
+///
+/// Precarga todos los sonidos, mezcla los canales de música y efectos con el
+/// volumen maestro y baja la música mientras suena un efecto de victoria o
+/// derrota. La música se funde con una capa de tensión según la intensidad
+/// pedida por el juego. Si no hay dispositivo de audio o
+/// falta un archivo, los sonidos afectados simplemente no suenan.
+pub struct AudioManager {
+    // El stream debe vivir mientras se reproduce audio
+    _stream: Option<OutputStream>,
+    handle: Option<OutputStreamHandle>,
+    clips: HashMap<Sound, Clip>,
+    music: Option<Sink>,
+    tension: Option<Sink>,
+    music_intensity: f32,
+    target_intensity: f32,
+    last_update: Instant,
+    footsteps: Option<Sink>,
+    enemy: Option<PositionalLoop>,
+    stingers: Vec<Sink>,
+    master_volume: f32,
+    music_volume: f32,
+    sfx_volume: f32,
+}
+
+impl AudioManager {
+    /// Abre el dispositivo por defecto y carga los sonidos de `asset_dir`.
+    pub fn new(asset_dir: &str) -> Self {
+        let (stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("Audio disabled: {}", e);
+                return AudioManager::null();
+            }
+        };
+
+        let mut clips = HashMap::new();
+        for sound in Sound::ALL {
+            let Some(file_name) = sound.file_name() else {
+                clips.insert(sound, synthesize(sound));
+                continue;
+            };
+            let path = Path::new(asset_dir).join(file_name);
+            match load_clip(&path) {
+                Ok(clip) => {
+                    clips.insert(sound, clip);
+                }
+                Err(e) => eprintln!("Failed to load {}: {}", path.display(), e),
+            }
+        }
+
+        AudioManager {
+            _stream: Some(stream),
+            handle: Some(handle),
+            clips,
+            ..AudioManager::null()
+        }
+    }
+
+    /// Un gestor sin dispositivo: acepta todas las llamadas y no reproduce nada.
+    pub fn null() -> Self {
+        AudioManager {
+            _stream: None,
+            handle: None,
+            clips: HashMap::new(),
+            music: None,
+            tension: None,
+            music_intensity: 0.0,
+            target_intensity: 0.0,
+            last_update: Instant::now(),
+            footsteps: None,
+            enemy: None,
+            stingers: Vec::new(),
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+        }
+    }
+
+    pub fn set_volumes(&mut self, master: f32, music: f32, sfx: f32) {
+        self.master_volume = master;
+        self.music_volume = music;
+        self.sfx_volume = sfx;
+        self.apply_volumes();
+    }
+
+    /// Empieza la música de fondo en bucle (no hace nada si ya suena).
+    pub fn play_music(&mut self) {
+        if self.music.is_none() {
+            self.music = self.looping_sink(Sound::Music);
+            self.tension = self.looping_sink(Sound::Tension);
+            self.apply_volumes();
+        }
+    }
+
+    /// Intensidad deseada de la música entre 0.0 (calma) y 1.0 (peligro).
+    /// El cambio real es gradual y se aplica en `update`.
+    pub fn set_music_intensity(&mut self, intensity: f32) {
+        self.target_intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    /// Activa o detiene el bucle de pasos del jugador.
+    pub fn set_footsteps(&mut self, walking: bool) {
+        if walking && self.footsteps.is_none() {
+            self.footsteps = self.looping_sink(Sound::Footsteps);
+            self.apply_volumes();
+        } else if !walking {
+            if let Some(sink) = self.footsteps.take() {
+                sink.stop();
+            }
+        }
+    }
+
+    /// Coloca los pasos y la respiración del monstruo en la mezcla; `None`
+    /// los silencia.
+    pub fn set_enemy(&mut self, position: Option<Positional>) {
+        let Some(position) = position else {
+            if let Some(enemy) = self.enemy.take() {
+                enemy.sink.stop();
+            }
+            return;
+        };
+
+        if self.enemy.is_none() {
+            self.enemy = self.positional_loop(&[Sound::Footsteps, Sound::Breathing]);
+            self.apply_volumes();
+        }
+        if let Some(enemy) = &self.enemy {
+            *enemy.gains.lock().unwrap() = position.channel_gains();
+        }
+    }
+
+    /// Reproduce un efecto una vez bajando la música hasta que termine.
+    pub fn play_stinger(&mut self, sound: Sound) {
+        if let Some(sink) = self.sink_with(sound, |clip| Box::new(clip)) {
+            self.stingers.push(sink);
+            self.apply_volumes();
+        }
+    }
+
+    /// Debe llamarse una vez por cuadro: libera los efectos terminados,
+    /// restaura la música cuando ya no queda ninguno sonando y acerca la
+    /// intensidad de la música a la pedida.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let max_step = (now - self.last_update).as_secs_f32() * INTENSITY_FADE_PER_SEC;
+        self.last_update = now;
+
+        let before = self.stingers.len();
+        self.stingers.retain(|sink| !sink.empty());
+
+        let delta = (self.target_intensity - self.music_intensity).clamp(-max_step, max_step);
+        self.music_intensity += delta;
+
+        if self.stingers.len() != before || delta != 0.0 {
+            self.apply_volumes();
+        }
+    }
+
+    fn sfx_gain(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    fn apply_volumes(&self) {
+        let duck = if self.stingers.is_empty() {
+            1.0
+        } else {
+            DUCKED_MUSIC
+        };
+        // Fundido de igual potencia entre la música y la capa de tensión
+        let music_gain = self.master_volume * self.music_volume * duck;
+        let fade = self.music_intensity * FRAC_PI_2;
+        if let Some(music) = &self.music {
+            music.set_volume(music_gain * fade.cos());
+        }
+        if let Some(tension) = &self.tension {
+            tension.set_volume(music_gain * fade.sin());
+        }
+        if let Some(footsteps) = &self.footsteps {
+            footsteps.set_volume(self.sfx_gain());
+        }
+        if let Some(enemy) = &self.enemy {
+            enemy.sink.set_volume(self.sfx_gain());
+        }
+        for stinger in &self.stingers {
+            stinger.set_volume(self.sfx_gain());
+        }
+    }
+
+    fn looping_sink(&self, sound: Sound) -> Option<Sink> {
+        self.sink_with(sound, |clip| Box::new(Looping::new(clip)))
+    }
+
+    // Un bucle con `sounds` mezclados; los que no se cargaron no suenan
+    fn positional_loop(&self, sounds: &[Sound]) -> Option<PositionalLoop> {
+        let handle = self.handle.as_ref()?;
+        let mut layers = sounds.iter().filter_map(|sound| {
+            let clip = self.clips.get(sound)?.clone();
+            Some(Looping::new(clip).amplify(sound.gain()))
+        });
+        let first: Box<dyn Source<Item = i16> + Send> = Box::new(layers.next()?);
+        let mixed = layers.fold(first, |mixed, layer| Box::new(mixed.mix(layer)));
+
+        // Mezcla a mono y reparte en dos canales con las ganancias actuales
+        let gains = Arc::new(Mutex::new([0.0, 0.0]));
+        let shared = Arc::clone(&gains);
+        let source = ChannelVolume::new(mixed, vec![0.0, 0.0]).periodic_access(
+            Duration::from_millis(10),
+            move |source| {
+                let gains = *shared.lock().unwrap();
+                source.set_volume(0, gains[0]);
+                source.set_volume(1, gains[1]);
+            },
+        );
+        let sink = Sink::try_new(handle).ok()?;
+        sink.append(source);
+        sink.play();
+        Some(PositionalLoop { sink, gains })
+    }
+
+    fn sink_with(
+        &self,
+        sound: Sound,
+        wrap: impl FnOnce(Clip) -> Box<dyn Source<Item = i16> + Send>,
+    ) -> Option<Sink> {
+        let handle = self.handle.as_ref()?;
+        let clip = self.clips.get(&sound)?.clone();
+        let sink = Sink::try_new(handle).ok()?;
+        sink.append(wrap(clip).amplify(sound.gain()));
+        sink.play();
+        Some(sink)
+    }
+}
+
+fn load_clip(path: &Path) -> Result<Clip, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let decoder = Decoder::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let source: Box<dyn Source<Item = i16> + Send> = Box::new(decoder);
+    Ok(source.buffered())
+}
+
+// Los sonidos que no vienen de un archivo
+fn synthesize(sound: Sound) -> Clip {
+    let samples = match sound {
+        Sound::Tension => drone(),
+        Sound::Breathing => breathing(),
+        _ => Vec::new(),
+    };
+    let source: Box<dyn Source<Item = i16> + Send> =
+        Box::new(SamplesBuffer::new(1, SYNTH_RATE, samples));
+    source.buffered()
+}
+
+// La capa de tensión: parciales graves que baten entre sí, con una onda lenta
+// de volumen que completa dos ciclos en el bucle
+fn drone() -> Vec<i16> {
+    let length = DRONE_LENGTH * SYNTH_RATE;
+    let total: f32 = DRONE_PARTIALS.iter().map(|&(_, amplitude)| amplitude).sum();
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / SYNTH_RATE as f32;
+            let swell = 0.75 + 0.25 * (TAU * 2.0 * t / DRONE_LENGTH as f32).sin();
+            let value: f32 = DRONE_PARTIALS
+                .iter()
+                .map(|&(frequency, amplitude)| amplitude * (TAU * frequency * t).sin())
+                .sum();
+            (value / total * swell * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+// Un ciclo de respiración lenta y ronca: ruido filtrado con una inhalación
+// corta y más aguda, una pausa y una exhalación larga y más grave
+fn breathing() -> Vec<i16> {
+    let length = (BREATH_CYCLE * SYNTH_RATE as f32) as usize;
+    let mut filtered = 0.0;
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / length as f32;
+            let (envelope, brightness) = if t < 0.35 {
+                ((t / 0.35 * PI).sin().powi(2), 0.3)
+            } else if (0.45..0.9).contains(&t) {
+                (0.8 * ((t - 0.45) / 0.45 * PI).sin().powi(2), 0.12)
+            } else {
+                (0.0, 0.12)
+            };
+            let noise = crate::lighting::noise(i as u64) * 2.0 - 1.0;
+            filtered += (noise - filtered) * brightness;
+            let value = (filtered * envelope * 3.0).clamp(-1.0, 1.0);
+            (value * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+// Repite un clip sin volver a decodificarlo: al terminar empieza otro clon que
+// comparte las mismas muestras (`repeat_infinite` las copiaría a un segundo buffer).
+struct Looping {
+    clip: Clip,
+    current: Clip,
+}
+
+impl Looping {
+    fn new(clip: Clip) -> Self {
+        Looping {
+            current: clip.clone(),
+            clip,
+        }
+    }
+}
+
+impl Iterator for Looping {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        self.current.next().or_else(|| {
+            self.current = self.clip.clone();
+            self.current.next()
+        })
+    }
+}
+
+impl Source for Looping {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self.current.current_frame_len() {
+            Some(0) => self.clip.current_frame_len(),
+            len => len,
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.current.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_manager_accepts_every_call() {
+        let mut audio = AudioManager::null();
+        audio.set_volumes(0.5, 0.8, 0.3);
+        audio.play_music();
+        audio.set_footsteps(true);
+        audio.set_enemy(Some(Positional::new((1.5, 1.5), 0.0, (3.5, 1.5), false)));
+        audio.play_stinger(Sound::GameOver);
+        audio.set_music_intensity(2.0);
+
+        audio.last_update -= Duration::from_secs(1);
+        audio.update();
+        assert_eq!(audio.target_intensity, 1.0);
+        // Un segundo después la intensidad avanzó solo lo que permite el fundido
+        assert!((audio.music_intensity - INTENSITY_FADE_PER_SEC).abs() < 0.01);
+        assert!(audio.music.is_none() && audio.enemy.is_none() && audio.stingers.is_empty());
+
+        audio.set_enemy(None);
+        audio.set_footsteps(false);
+        audio.update();
+    }
+
+    #[test]
+    fn synthesized_sounds_loop_without_clicks() {
+        for samples in [drone(), breathing()] {
+            assert!(samples.iter().any(|&sample| sample.abs() > i16::MAX / 8));
+            let seam = samples[0] as i32 - samples[samples.len() - 1] as i32;
+            assert!(seam.abs() < i16::MAX as i32 / 50, "seam {}", seam);
+        }
+    }
+}
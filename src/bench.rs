@@ -0,0 +1,306 @@
+use crate::camera::View;
+use crate::cli::Options;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::maze::is_floor;
+use crate::minimap::Minimap;
+use crate::player::Player;
+use crate::postfx::{self, PostEffects};
+use crate::render::{render3d, timed, Scene, StageTimes};
+use crate::text::TextStyle;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+// Celdas que avanza la cámara por cuadro a lo largo del recorrido
+const CAMERA_SPEED: f32 = 0.05;
+
+// Amplitud (radianes) y periodo (cuadros) del barrido de la cámara
+const SWEEP_AMPLITUDE: f32 = 0.6;
+const SWEEP_PERIOD: f32 = 240.0;
+
+const STAGES: [&str; 6] = ["rays", "walls", "floor", "sprites", "post", "hud"];
+
+/// Tiempos de un cuadro del benchmark, por etapa.
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameSample {
+    stages: StageTimes,
+    total: Duration,
+}
+
+impl FrameSample {
+    fn stage(&self, index: usize) -> Duration {
+        match index {
+            0 => self.stages.rays,
+            1 => self.stages.walls,
+            2 => self.stages.floor,
+            3 => self.stages.sprites,
+            4 => self.stages.post,
+            _ => self.stages.hud,
+        }
+    }
+}
+
+/// Mínimo, promedio y percentil 99 de una serie de tiempos, en milisegundos.
+#[derive(Debug, Clone, Copy)]
+struct Summary {
+    min: f64,
+    avg: f64,
+    p99: f64,
+}
+
+impl Summary {
+    fn new(mut times: Vec<Duration>) -> Self {
+        times.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let total: Duration = times.iter().sum();
+        let p99 = ((times.len() as f64 * 0.99).ceil() as usize).clamp(1, times.len()) - 1;
+        Summary {
+            min: ms(times[0]),
+            avg: ms(total) / times.len() as f64,
+            p99: ms(times[p99]),
+        }
+    }
+}
+
+/// Recorre el nivel sin ventana ni audio durante `frames` cuadros, midiendo
+/// cada etapa del render, e imprime el informe (y lo guarda si se pidió).
+pub fn run_benchmark(options: &Options, frames: usize) {
+    let seed = options.seed.unwrap_or(0);
+    let (mut player, sprite, mut maze, mut doors, items, lightmap) = crate::new_run(
+        &options.level,
+        seed,
+        options.difficulty,
+        std::f32::consts::PI / 3.0,
+    )
+    .unwrap_or_else(|e| crate::exit_with_usage(&e));
+    // El recorrido atraviesa las puertas, así que empiezan todas abiertas
+    doors.open_all(&mut maze);
+    let mut minimap = Minimap::new(&maze);
+    let path = camera_path(&maze, (player.pos.x as usize, player.pos.y as usize));
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+
+    // Huella de todos los cuadros, para comparar la salida entre versiones o
+    // cantidades de hilos
+    let mut checksum = DefaultHasher::new();
+    let mut samples = Vec::with_capacity(frames);
+    for frame in 0..frames {
+        place_camera(&mut player, &path, frame);
+        minimap.reveal(&player, &maze, &doors, 100);
+
+        let start = Instant::now();
+        let mut sample = FrameSample::default();
+        framebuffer.clear();
+        let scene = Scene {
+            player: &player,
+            maze: &maze,
+            doors: &doors,
+            items: &items,
+            lightmap: &lightmap,
+            sprite: &sprite,
+            minimap: &minimap,
+            view: View::default(),
+            tick: frame as u64,
+        };
+        render3d(&mut framebuffer, &scene, &mut sample.stages);
+        timed(&mut sample.stages.post, || {
+            postfx::apply(&mut framebuffer, &PostEffects::default(), frame as u64)
+        });
+        timed(&mut sample.stages.hud, || {
+            let text = format!("Frame: {}", frame);
+            let style = TextStyle::new(framebuffer.text_size(32.0), Color::from_hex(0xFFFFFF))
+                .shadow(Color::from_hex(0x000000));
+            framebuffer.draw_text_styled(&text, 10, 10, &style);
+        });
+        sample.total = start.elapsed();
+
+        framebuffer.buffer.hash(&mut checksum);
+
+        samples.push(sample);
+    }
+
+    if samples.is_empty() {
+        return;
+    }
+
+    let report = format_text(options, seed, checksum.finish(), &samples);
+    print!("{}", report);
+
+    if let Some(path) = &options.benchmark_report {
+        let contents = if path.ends_with(".json") {
+            format_json(options, seed, checksum.finish(), &samples)
+        } else {
+            report
+        };
+        if let Err(e) = fs::write(path, contents) {
+            eprintln!("Failed to write benchmark report to {}: {}", path, e);
+        }
+    }
+}
+
+// Camino más corto (en celdas del laberinto) desde el inicio hasta la meta.
+// Si no hay meta alcanzable la cámara se queda en el inicio y solo gira.
+fn camera_path(maze: &[Vec<char>], start: (usize, usize)) -> Vec<(f32, f32)> {
+    let open = |x: usize, y: usize| {
+        maze.get(y)
+            .and_then(|row| row.get(x))
+            .is_some_and(|&c| is_floor(c) || c == 'g')
+    };
+
+    // Las filas del nivel pueden tener largos distintos
+    let mut previous: Vec<Vec<Option<(usize, usize)>>> =
+        maze.iter().map(|row| vec![None; row.len()]).collect();
+    let mut queue = VecDeque::from([start]);
+    let mut goal = None;
+
+    while let Some((x, y)) = queue.pop_front() {
+        if maze[y][x] == 'g' {
+            goal = Some((x, y));
+            break;
+        }
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if open(nx, ny) && previous[ny][nx].is_none() && (nx, ny) != start {
+                previous[ny][nx] = Some((x, y));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    let mut cells = vec![goal.unwrap_or(start)];
+    while let Some(cell) = previous[cells[cells.len() - 1].1][cells[cells.len() - 1].0] {
+        cells.push(cell);
+    }
+    cells.reverse();
+
+    cells
+        .into_iter()
+        .map(|(x, y)| (x as f32 + 0.5, y as f32 + 0.5))
+        .collect()
+}
+
+// Coloca la cámara en el cuadro `frame`: avanza por el camino, vuelve al
+// llegar al final y barre la vista a ambos lados de la dirección de avance.
+fn place_camera(player: &mut Player, path: &[(f32, f32)], frame: usize) {
+    let sweep = (frame as f32 / SWEEP_PERIOD * std::f32::consts::TAU).sin() * SWEEP_AMPLITUDE;
+
+    if path.len() < 2 {
+        player.angle = frame as f32 * std::f32::consts::TAU / SWEEP_PERIOD;
+        return;
+    }
+
+    let segments = (path.len() - 1) as f32;
+    let travelled = (frame as f32 * CAMERA_SPEED) % (segments * 2.0);
+    let (along, forward) = if travelled <= segments {
+        (travelled, true)
+    } else {
+        (segments * 2.0 - travelled, false)
+    };
+
+    let index = (along as usize).min(path.len() - 2);
+    let t = along - index as f32;
+    let (from, to) = (path[index], path[index + 1]);
+    player.pos.x = from.0 + (to.0 - from.0) * t;
+    player.pos.y = from.1 + (to.1 - from.1) * t;
+
+    let heading = (to.1 - from.1).atan2(to.0 - from.0);
+    let heading = if forward {
+        heading
+    } else {
+        heading + std::f32::consts::PI
+    };
+    player.angle = heading + sweep;
+}
+
+fn format_text(options: &Options, seed: u64, checksum: u64, samples: &[FrameSample]) -> String {
+    let mut report = format!(
+        "benchmark: {} frames at {}x{} on {} threads\nlevel: {}\nseed: {}\nchecksum: {:016x}\n\n{:<10}{:>10}{:>10}{:>10}\n",
+        samples.len(),
+        options.width,
+        options.height,
+        rayon::current_num_threads(),
+        options.level,
+        seed,
+        checksum,
+        "stage (ms)",
+        "min",
+        "avg",
+        "p99"
+    );
+
+    let mut row = |name: &str, summary: Summary| {
+        report += &format!(
+            "{:<10}{:>10.3}{:>10.3}{:>10.3}\n",
+            name, summary.min, summary.avg, summary.p99
+        );
+    };
+    for (index, name) in STAGES.iter().enumerate() {
+        row(name, stage_summary(samples, index));
+    }
+    let frame = frame_summary(samples);
+    row("frame", frame);
+
+    report + &format!("\navg fps: {:.1}\n", 1000.0 / frame.avg)
+}
+
+fn format_json(options: &Options, seed: u64, checksum: u64, samples: &[FrameSample]) -> String {
+    let entry = |summary: Summary| {
+        format!(
+            "{{\"min_ms\": {:.4}, \"avg_ms\": {:.4}, \"p99_ms\": {:.4}}}",
+            summary.min, summary.avg, summary.p99
+        )
+    };
+
+    let stages: Vec<String> = STAGES
+        .iter()
+        .enumerate()
+        .map(|(index, name)| format!("    \"{}\": {}", name, entry(stage_summary(samples, index))))
+        .collect();
+    let frame = frame_summary(samples);
+
+    format!(
+        "{{\n  \"frames\": {},\n  \"width\": {},\n  \"height\": {},\n  \"level\": \"{}\",\n  \"seed\": {},\n  \"threads\": {},\n  \"checksum\": \"{:016x}\",\n  \"stages\": {{\n{}\n  }},\n  \"frame\": {},\n  \"avg_fps\": {:.1}\n}}\n",
+        samples.len(),
+        options.width,
+        options.height,
+        options.level.replace('\\', "\\\\").replace('"', "\\\""),
+        seed,
+        rayon::current_num_threads(),
+        checksum,
+        stages.join(",\n"),
+        entry(frame),
+        1000.0 / frame.avg
+    )
+}
+
+fn stage_summary(samples: &[FrameSample], index: usize) -> Summary {
+    Summary::new(samples.iter().map(|s| s.stage(index)).collect())
+}
+
+fn frame_summary(samples: &[FrameSample]) -> Summary {
+    Summary::new(samples.iter().map(|s| s.total).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_path_handles_rows_of_different_lengths() {
+        let maze: Vec<Vec<char>> = ["+-+", "| |", "|    g|", "+-----+"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+
+        let path = camera_path(&maze, (1, 1));
+        assert_eq!(path.first(), Some(&(1.5, 1.5)));
+        assert_eq!(path.last(), Some(&(5.5, 2.5)));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::lighting::noise;
+use std::f32::consts::TAU;
+
+// Celdas recorridas en un ciclo completo del balanceo (dos pasos)
+const BOB_STRIDE: f32 = 0.6;
+
+// Altura del balanceo caminando a toda velocidad, en fracciones del alto de
+// la pantalla
+const BOB_AMPLITUDE: f32 = 0.012;
+
+// Cuánto se acerca la amplitud del balanceo a la de la velocidad actual en
+// cada tick, para que empiece y pare con suavidad
+const BOB_SMOOTHING: f32 = 0.1;
+
+// Temblor que se pierde por tick (un temblor completo dura ~1 segundo)
+const SHAKE_DECAY: f32 = 1.0 / 60.0;
+
+// Desplazamiento máximo del temblor, en fracciones del alto de la pantalla
+const SHAKE_AMPLITUDE: f32 = 0.03;
+
+// Ticks que dura cada posición del temblor
+const SHAKE_TICKS: u64 = 2;
+
+/// Temblor al ser visto por el monstruo y al recibir un golpe.
+pub const SPOTTED_SHAKE: f32 = 0.5;
+pub const HIT_SHAKE: f32 = 0.9;
+
+/// Movimiento de la cámara al caminar y temblor por los sustos. Solo cambia
+/// cómo se dibuja la vista, no la simulación, pero avanza con ella para que un
+/// replay se vea igual.
+#[derive(Debug, Clone, Default)]
+pub struct CameraMotion {
+    phase: f32,
+    // 0.0 quieto, 1.0 caminando a toda velocidad
+    bob: f32,
+    /// Intensidad del temblor entre 0.0 y 1.0; baja sola con el tiempo.
+    pub trauma: f32,
+    // Si el monstruo veía al jugador el tick anterior
+    spotted: bool,
+}
+
+/// Desplazamiento de la vista 3D en un cuadro, en fracciones del alto de la
+/// pantalla: `pitch` mueve el horizonte hacia abajo y `shift` la imagen hacia
+/// la derecha.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct View {
+    pub pitch: f32,
+    pub shift: f32,
+}
+
+impl CameraMotion {
+    /// Avanza un tick: el balanceo sigue a lo que caminó el jugador (en
+    /// celdas) respecto a su velocidad, y el temblor se calma.
+    pub fn update(&mut self, walked: f32, speed: f32) {
+        let target = (walked / speed).clamp(0.0, 1.0);
+        self.bob += (target - self.bob) * BOB_SMOOTHING;
+        self.phase = (self.phase + walked / BOB_STRIDE * TAU) % TAU;
+        self.trauma = (self.trauma - SHAKE_DECAY).max(0.0);
+    }
+
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    /// Sacude la cámara cuando el monstruo empieza a ver al jugador.
+    pub fn set_spotted(&mut self, spotted: bool) {
+        if spotted && !self.spotted {
+            self.shake(SPOTTED_SHAKE);
+        }
+        self.spotted = spotted;
+    }
+
+    /// Desplazamiento de la vista en el tick dado. `bob` y `shake` escalan
+    /// cada efecto (0.0 lo desactiva).
+    pub fn view(&self, bob: f32, shake: f32, tick: u64) -> View {
+        let bob = self.phase.sin() * self.bob * BOB_AMPLITUDE * bob;
+
+        // El temblor crece con el cuadrado para que los sustos leves apenas se noten
+        let strength = self.trauma * self.trauma * SHAKE_AMPLITUDE * shake;
+        let jitter = |axis: u64| (noise((tick / SHAKE_TICKS) * 2 + axis) - 0.5) * 2.0;
+
+        View {
+            pitch: bob + strength * jitter(0),
+            shift: strength * jitter(1),
+        }
+    }
+}
@@ -1,53 +1,274 @@
 use crate::color::Color;
+use crate::doors::{is_door, Doors};
 use crate::framebuffer::Framebuffer;
+use crate::maze::{is_floor, is_transparent, thin_wall, wall_span, Span};
 use crate::player::Player;
-use crate::texture::Texture;
 
 pub struct Intersect {
     pub distance: f32,
-    pub impact: char,
+    /// Distancia a la que el rayo sale de la celda tocada.
+    pub exit: f32,
     pub tx: usize,
+    /// Carácter de la celda tocada, para texturizar cada tipo de pared.
+    pub cell: char,
+    /// Alturas entre las que está la pared.
+    pub span: Span,
 }
 
+// Si se pasa un framebuffer, el recorrido del rayo se dibuja en él (vista 2D).
+// Las puertas detienen el rayo en su hoja, como en la vista 3D; las paredes
+// delgadas se cortan con el rayo al entrar en su celda y los vidrios y las
+// rejas se atraviesan.
 pub fn cast_rays(
-    framebuffer: &mut Framebuffer,
-    maze: &Vec<Vec<char>>,
+    mut framebuffer: Option<&mut Framebuffer>,
+    maze: &[Vec<char>],
+    doors: &Doors,
     player: &Player,
     angle: f32,
     block_size: usize,
-    draw_line: bool,
 ) -> Intersect {
     let mut d = 0.0;
 
     loop {
-        let cos = d * angle.cos();
-        let sin = d * angle.sin();
-        let x = (player.pos.x * block_size as f32 + cos) as usize;
-        let y = (player.pos.y * block_size as f32 + sin) as usize;
+        let fx = player.pos.x * block_size as f32 + d * angle.cos();
+        let fy = player.pos.y * block_size as f32 + d * angle.sin();
+        let (x, y) = (fx as usize, fy as usize);
 
         let i = x / block_size;
         let j = y / block_size;
 
-        if draw_line {
+        // Fuera del laberinto (tras un vidrio o una reja del borde) el rayo
+        // termina en el borde
+        let cell = maze.get(j).and_then(|row| row.get(i));
+        let Some(&cell) = cell.filter(|_| fx >= 0.0 && fy >= 0.0) else {
+            return Intersect {
+                distance: d,
+                exit: d,
+                tx: 0,
+                cell: ' ',
+                span: Span::FULL,
+            };
+        };
+
+        if let Some(framebuffer) = framebuffer.as_mut() {
             framebuffer.point_with_color(x, y, Color::from_hex(0xFF33DD)); // Dibuja el punto del rayo
         }
 
-        if maze[j][i] != ' ' {
-            let hitx = x - i * block_size;
-            let hity = y - j * block_size;
-            let mut maxhit = hity;
+        let hitx = x - i * block_size;
+        let hity = y - j * block_size;
 
-            if 1 < hitx && hitx < block_size - 1 {
-                maxhit = hitx;
+        if let Some(hit) = thin_hit(cell, (i, j), player, angle, block_size) {
+            return hit;
+        }
+        if is_door(cell) {
+            if let Some(hit) = door_hit(doors, cell, (i, j), (hitx, hity), d, block_size) {
+                return hit;
             }
-
+        } else if !is_floor(cell) && !is_transparent(cell) {
             return Intersect {
                 distance: d,
-                impact: maze[j][i],
-                tx: maxhit * 512 / block_size,
+                exit: d,
+                tx: face_tx(hitx, hity, block_size),
+                cell,
+                span: wall_span(cell).unwrap_or(Span::FULL),
             };
         }
 
         d += 1.0; // Incrementa d en pequeños pasos para mayor precisión
     }
 }
+
+/// Todo lo que toca el rayo de una columna de la vista 3D, de cerca a lejos:
+/// las paredes bajas, las vigas de los techos bajos, los vidrios y las rejas no
+/// lo detienen, así que se ve lo que hay detrás. Termina en la primera pared
+/// opaca que tapa todo hasta `max_height` (la pared más alta del nivel) o en
+/// una puerta.
+pub fn cast_column(
+    maze: &[Vec<char>],
+    doors: &Doors,
+    player: &Player,
+    angle: f32,
+    max_height: f32,
+    block_size: usize,
+) -> Vec<Intersect> {
+    let mut hits: Vec<Intersect> = Vec::new();
+    // Celda del paso anterior y el tramo que aún no sabe dónde sale el rayo
+    let mut current = None;
+    let mut open: Option<usize> = None;
+    let mut d = 0.0;
+
+    loop {
+        let fx = player.pos.x * block_size as f32 + d * angle.cos();
+        let fy = player.pos.y * block_size as f32 + d * angle.sin();
+        let (x, y) = (fx as usize, fy as usize);
+        let i = x / block_size;
+        let j = y / block_size;
+        let cell = maze.get(j).and_then(|row| row.get(i));
+
+        // Fuera del laberinto ya no hay nada que ver
+        let Some(&cell) = cell.filter(|_| fx >= 0.0 && fy >= 0.0) else {
+            if let Some(index) = open {
+                hits[index].exit = d;
+            }
+            return hits;
+        };
+        let hitx = x - i * block_size;
+        let hity = y - j * block_size;
+
+        if current != Some((i, j)) {
+            current = Some((i, j));
+            if let Some(index) = open.take() {
+                hits[index].exit = d;
+            }
+
+            if let Some(span) = wall_span(cell).filter(|_| !is_door(cell)) {
+                hits.push(Intersect {
+                    distance: d,
+                    exit: d,
+                    tx: face_tx(hitx, hity, block_size),
+                    cell,
+                    span,
+                });
+                if span.covers(max_height) && !is_transparent(cell) {
+                    return hits;
+                }
+                open = Some(hits.len() - 1);
+            }
+
+            if let Some(hit) = thin_hit(cell, (i, j), player, angle, block_size) {
+                hits.push(hit);
+                if Span::FULL.covers(max_height) {
+                    return hits;
+                }
+            }
+        }
+
+        if is_door(cell) {
+            if let Some(hit) = door_hit(doors, cell, (i, j), (hitx, hity), d, block_size) {
+                hits.push(hit);
+                return hits;
+            }
+        }
+
+        d += 1.0;
+    }
+}
+
+// Si el punto (hitx, hity) de la celda (i, j), a `distance` del jugador, está
+// en la hoja de su puerta: la hoja cruza la celda por el medio y se corre
+// hacia un lado al abrirse
+fn door_hit(
+    doors: &Doors,
+    cell: char,
+    (i, j): (usize, usize),
+    (hitx, hity): (usize, usize),
+    distance: f32,
+    block_size: usize,
+) -> Option<Intersect> {
+    let door = doors.at(i, j)?;
+    let (across, along) = if door.along_x {
+        (hitx, hity)
+    } else {
+        (hity, hitx)
+    };
+    let shift = (door.open * block_size as f32) as usize;
+    (across == block_size / 2 && along >= shift).then(|| Intersect {
+        distance,
+        exit: distance,
+        tx: (along - shift) * 512 / block_size,
+        cell,
+        span: Span::FULL,
+    })
+}
+
+// Dónde corta el rayo la pared delgada de la celda (i, j), si la tiene y el
+// rayo pasa por ella
+fn thin_hit(
+    cell: char,
+    (i, j): (usize, usize),
+    player: &Player,
+    angle: f32,
+    block_size: usize,
+) -> Option<Intersect> {
+    let segment = thin_wall(cell, i, j)?;
+    let origin = (player.pos.x, player.pos.y);
+    let (distance, along) = segment.intersect(origin, (angle.cos(), angle.sin()))?;
+    let distance = distance * block_size as f32;
+    Some(Intersect {
+        distance,
+        exit: distance,
+        tx: ((along * 512.0) as usize).min(511),
+        cell,
+        span: Span::FULL,
+    })
+}
+
+// Columna de la textura en el punto de la pared tocado: el lado que no está
+// pegado al borde de la celda
+fn face_tx(hitx: usize, hity: usize, block_size: usize) -> usize {
+    let mut maxhit = hity;
+
+    if 1 < hitx && hitx < block_size - 1 {
+        maxhit = hitx;
+    }
+
+    maxhit * 512 / block_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    #[test]
+    fn rays_through_a_border_window_stop_at_the_edge() {
+        let maze = level(&["+--+", "|  W", "+--+"]);
+        let doors = Doors::new(&maze);
+        let player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+
+        let hit = cast_rays(None, &maze, &doors, &player, 0.0, 100);
+        assert_eq!(hit.distance, 250.0);
+
+        let hits = cast_column(&maze, &doors, &player, 0.0, 1.0, 100);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].cell, 'W');
+        assert_eq!(hits[0].distance, 150.0);
+        assert_eq!(hits[0].exit, 250.0);
+    }
+
+    #[test]
+    fn rays_pass_the_gap_of_an_opening_door() {
+        let mut maze = level(&["+----+", "| D  |", "+----+"]);
+        let mut doors = Doors::new(&maze);
+        let mut player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+
+        let closed = cast_rays(None, &maze, &doors, &player, 0.0, 100);
+        assert_eq!((closed.cell, closed.distance), ('D', 100.0));
+
+        // A medio abrir la hoja ya no cruza el centro del pasillo
+        doors.interact(&mut player, &maze);
+        for _ in 0..40 {
+            doors.update(&mut maze, &[]);
+        }
+        let open = cast_rays(None, &maze, &doors, &player, 0.0, 100);
+        assert_eq!((open.cell, open.distance), ('|', 350.0));
+        let hits = cast_column(&maze, &doors, &player, 0.0, 1.0, 100);
+        assert_eq!(hits.last().map(|hit| hit.distance), Some(open.distance));
+    }
+
+    #[test]
+    fn columns_see_through_windows_to_the_wall_behind() {
+        let maze = level(&["+-----+", "|  W  |", "+-----+"]);
+        let doors = Doors::new(&maze);
+        let player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+
+        let hits = cast_column(&maze, &doors, &player, 0.0, 1.0, 100);
+        let cells: Vec<char> = hits.iter().map(|hit| hit.cell).collect();
+        assert_eq!(cells, ['W', '|']);
+        assert_eq!((hits[0].distance, hits[0].exit), (150.0, 250.0));
+        assert_eq!(hits[1].distance, 450.0);
+    }
+}
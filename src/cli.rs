@@ -0,0 +1,280 @@
+use crate::framebuffer::Filter;
+
+/// Dificultad: controla la velocidad del monstruo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn sprite_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.005,
+            Difficulty::Normal => 0.007,
+            Difficulty::Hard => 0.010,
+        }
+    }
+}
+
+/// Opciones de línea de comandos del juego.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Archivo de nivel, o `generated <columnas>x<filas>` para un laberinto aleatorio.
+    pub level: String,
+    /// Semilla fija para el generador de laberintos y la partida.
+    pub seed: Option<u64>,
+    pub width: usize,
+    pub height: usize,
+    /// Escala de render que reemplaza la de `settings.cfg`.
+    pub render_scale: Option<f32>,
+    pub upscale: Option<Filter>,
+    pub window_scale: f32,
+    pub fullscreen: bool,
+    pub difficulty: Difficulty,
+    pub asset_dir: String,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub headless: bool,
+    /// Cuadros a renderizar en modo benchmark.
+    pub benchmark: Option<usize>,
+    pub benchmark_report: Option<String>,
+    /// Hilos para el render; `None` usa uno por núcleo.
+    pub threads: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            level: "./maze.txt".to_string(),
+            seed: None,
+            width: 1300,
+            height: 900,
+            render_scale: None,
+            upscale: None,
+            window_scale: 1.3,
+            fullscreen: false,
+            difficulty: Difficulty::Normal,
+            asset_dir: "./assets".to_string(),
+            record: None,
+            replay: None,
+            headless: false,
+            benchmark: None,
+            benchmark_report: None,
+            threads: None,
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: Proyecto1-GC [options]
+
+  --level <file>          Maze file to play (default ./maze.txt)
+  --generate <cols>x<rows> Play a randomly generated maze of the given size
+  --seed <n>              Seed for the maze generator and the run
+  --resolution <w>x<h>    Internal render resolution (default 1300x900)
+  --render-scale <f>      Render at 0.25-1.0 of the resolution and upscale
+  --upscale <filter>      nearest or bilinear upscaling to the window
+  --window-scale <f>      Window size divisor (default 1.3)
+  --fullscreen            Borderless window at the render resolution
+  --difficulty <d>        easy, normal or hard (default normal)
+  --assets <dir>          Asset directory (default ./assets)
+  --record <file>         Record each run to a replay file
+  --replay <file>         Play back a replay file
+  --headless              Run the replay without window or audio
+  --benchmark <frames>    Render a scripted fly-through headlessly and report timings
+  --benchmark-report <file> Also write the report to a file (JSON if it ends in .json)
+  --threads <n>           Render threads (default: one per CPU core)
+  --help                  Show this message";
+
+/// Interpreta los argumentos (sin el nombre del programa).
+/// Devuelve `Ok(None)` si se pidió la ayuda.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--level" => options.level = value()?,
+            "--generate" => {
+                let (cols, rows) = parse_size(&value()?)?;
+                if cols < 2 || rows < 2 {
+                    return Err("--generate needs at least 2x2 cells".to_string());
+                }
+                options.level = format!("generated {}x{}", cols, rows);
+            }
+            "--seed" => options.seed = Some(parse_number(&value()?)?),
+            "--resolution" => {
+                let (width, height) = parse_size(&value()?)?;
+                options.width = width;
+                options.height = height;
+            }
+            "--render-scale" => {
+                let scale: f32 = parse_number(&value()?)?;
+                if !(0.25..=1.0).contains(&scale) {
+                    return Err("--render-scale must be between 0.25 and 1.0".to_string());
+                }
+                options.render_scale = Some(scale);
+            }
+            "--upscale" => {
+                let name = value()?;
+                options.upscale = Some(
+                    Filter::from_name(&name)
+                        .ok_or_else(|| format!("Unknown upscale filter: {}", name))?,
+                );
+            }
+            "--window-scale" => {
+                let scale: f32 = parse_number(&value()?)?;
+                if scale <= 0.0 {
+                    return Err("--window-scale must be positive".to_string());
+                }
+                options.window_scale = scale;
+            }
+            "--fullscreen" => options.fullscreen = true,
+            "--difficulty" => {
+                let name = value()?;
+                options.difficulty = Difficulty::from_name(&name)
+                    .ok_or_else(|| format!("Unknown difficulty: {}", name))?;
+            }
+            "--assets" => options.asset_dir = value()?,
+            "--record" => options.record = Some(value()?),
+            "--replay" => options.replay = Some(value()?),
+            "--headless" => options.headless = true,
+            "--benchmark" => {
+                let frames: usize = parse_number(&value()?)?;
+                if frames == 0 {
+                    return Err("--benchmark needs at least 1 frame".to_string());
+                }
+                options.benchmark = Some(frames);
+            }
+            "--benchmark-report" => options.benchmark_report = Some(value()?),
+            "--threads" => {
+                let threads: usize = parse_number(&value()?)?;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+                options.threads = Some(threads);
+            }
+            "--help" | "-h" => return Ok(None),
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    if options.headless && options.replay.is_none() {
+        return Err("--headless requires --replay <file>".to_string());
+    }
+    if options.benchmark_report.is_some() && options.benchmark.is_none() {
+        return Err("--benchmark-report requires --benchmark <frames>".to_string());
+    }
+    if let Some(scale) = options.render_scale {
+        let scaled = |size: usize| (size as f32 * scale) as usize;
+        if scaled(options.width) == 0 || scaled(options.height) == 0 {
+            return Err(format!(
+                "--render-scale {} leaves no pixels at {}x{}",
+                scale, options.width, options.height
+            ));
+        }
+    }
+
+    Ok(Some(options))
+}
+
+/// Interpreta un tamaño con el formato `<ancho>x<alto>`.
+pub fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid size: {} (expected <w>x<h>)", text);
+    let (w, h) = text.split_once('x').ok_or_else(invalid)?;
+    let w: usize = w.parse().map_err(|_| invalid())?;
+    let h: usize = h.parse().map_err(|_| invalid())?;
+    if w == 0 || h == 0 {
+        return Err(invalid());
+    }
+    Ok((w, h))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("Invalid number: {}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let options = parse(&[
+            "--generate",
+            "8x6",
+            "--seed",
+            "42",
+            "--resolution",
+            "640x480",
+            "--render-scale",
+            "0.5",
+            "--upscale",
+            "bilinear",
+            "--difficulty",
+            "hard",
+            "--replay",
+            "run.replay",
+            "--headless",
+            "--threads",
+            "2",
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(options.level, "generated 8x6");
+        assert_eq!(options.seed, Some(42));
+        assert_eq!((options.width, options.height), (640, 480));
+        assert_eq!(options.render_scale, Some(0.5));
+        assert_eq!(options.upscale, Some(Filter::Bilinear));
+        assert_eq!(options.difficulty, Difficulty::Hard);
+        assert_eq!(options.replay.as_deref(), Some("run.replay"));
+        assert!(options.headless);
+        assert_eq!(options.threads, Some(2));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--resolution", "640"]).is_err());
+        assert!(parse(&["--headless"]).is_err());
+        assert!(parse(&["--benchmark-report", "out.json"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+    }
+
+    #[test]
+    fn render_scale_keeps_at_least_one_pixel() {
+        assert!(parse(&["--resolution", "2x2", "--render-scale", "0.25"]).is_err());
+        assert!(parse(&["--resolution", "4x4", "--render-scale", "0.25"]).is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::ops::{Add, Mul};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -9,25 +9,6 @@ pub struct Color {
 }
 
 impl Color {
-    // Constructor que recibe valores RGB y hace clamping
-    pub fn new(r: i32, g: i32, b: i32) -> Self {
-        Self {
-            r: Self::clamp(r),
-            g: Self::clamp(g),
-            b: Self::clamp(b),
-        }
-    }
-
-    fn clamp(value: i32) -> u8 {
-        if value < 0 {
-            0
-        } else if value > 255 {
-            255
-        } else {
-            value as u8
-        }
-    }
-
     // Constructor que recibe un valor hexadecimal u32
     pub fn from_hex(hex: u32) -> Color {
         Color {
@@ -38,7 +19,7 @@ impl Color {
     }
 
     // Método que retorna el valor hexadecimal u32 del color
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 }
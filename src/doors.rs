@@ -0,0 +1,207 @@
+use crate::items::ItemKind;
+use crate::maze::is_floor;
+use crate::player::Player;
+
+// Fracción de apertura que avanza una puerta por tick (~1 segundo en abrirse)
+const DOOR_SPEED: f32 = 1.0 / 60.0;
+
+// Distancia máxima (en celdas) a la que se puede usar una puerta o un interruptor
+const REACH: f32 = 1.5;
+const REACH_STEP: f32 = 0.05;
+
+/// Celda de puerta que se abre con Interactuar.
+pub const DOOR: char = 'D';
+/// Puerta cerrada con llave: se abre con Interactuar gastando una llave.
+pub const LOCKED_DOOR: char = 'L';
+/// Puerta que solo abren los interruptores.
+pub const SWITCH_DOOR: char = 'd';
+/// Pared con un interruptor que abre o cierra todas las puertas `d`.
+pub const SWITCH: char = 'S';
+
+pub fn is_door(cell: char) -> bool {
+    matches!(cell, DOOR | LOCKED_DOOR | SWITCH_DOOR)
+}
+
+/// Resultado de usar la celda que el jugador tiene enfrente.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interaction {
+    Opened,
+    Closed,
+    Unlocked,
+    Locked,
+    Switched,
+    NeedsSwitch,
+}
+
+impl Interaction {
+    /// Mensaje para el HUD, si la acción lo merece.
+    pub fn message(self) -> Option<&'static str> {
+        match self {
+            Interaction::Unlocked => Some("Unlocked"),
+            Interaction::Locked => Some("Locked. You need a key"),
+            Interaction::Switched => Some("Somewhere, a door moves"),
+            Interaction::NeedsSwitch => Some("It won't budge. There must be a switch"),
+            Interaction::Opened | Interaction::Closed => None,
+        }
+    }
+}
+
+/// Una puerta: una hoja en el centro de su celda que se desliza hacia un lado.
+pub struct Door {
+    pub x: usize,
+    pub y: usize,
+    /// `DOOR`, `LOCKED_DOOR` o `SWITCH_DOOR`.
+    pub kind: char,
+    /// 0.0 cerrada, 1.0 completamente abierta.
+    pub open: f32,
+    opening: bool,
+    /// El pasillo corre a lo largo de x, así que la hoja es perpendicular a x.
+    pub along_x: bool,
+}
+
+/// Estado de todas las puertas del nivel.
+///
+/// Mientras una puerta no está del todo abierta su carácter sigue en el
+/// laberinto y bloquea al jugador, al monstruo y a los rayos; al terminar de
+/// abrirse la celda pasa a ser suelo (`' '`) y vuelve a su carácter al cerrarse.
+pub struct Doors {
+    doors: Vec<Door>,
+}
+
+impl Doors {
+    pub fn new(maze: &[Vec<char>]) -> Self {
+        let mut doors = Vec::new();
+        for (y, row) in maze.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if is_door(cell) {
+                    // Hueco en una pared `|`: se pasa de izquierda a derecha
+                    let open_at = |dx: isize| {
+                        row.get(x.wrapping_add_signed(dx))
+                            .is_some_and(|&c| is_floor(c) || c == 'g')
+                    };
+                    doors.push(Door {
+                        x,
+                        y,
+                        kind: cell,
+                        open: 0.0,
+                        opening: false,
+                        along_x: open_at(-1) || open_at(1),
+                    });
+                }
+            }
+        }
+        Doors { doors }
+    }
+
+    pub fn at(&self, x: usize, y: usize) -> Option<&Door> {
+        self.doors.iter().find(|door| door.x == x && door.y == y)
+    }
+
+    /// Usa la puerta o el interruptor que el jugador tiene enfrente.
+    pub fn interact(&mut self, player: &mut Player, maze: &[Vec<char>]) -> Option<Interaction> {
+        let (x, y) = self.target(player, maze)?;
+
+        if maze[y][x] == SWITCH {
+            let close = self
+                .doors
+                .iter()
+                .any(|door| door.kind == SWITCH_DOOR && door.opening);
+            for door in self.doors.iter_mut().filter(|d| d.kind == SWITCH_DOOR) {
+                door.opening = !close;
+            }
+            return Some(Interaction::Switched);
+        }
+
+        let index = self.doors.iter().position(|d| d.x == x && d.y == y)?;
+        let interaction = match self.doors[index].kind {
+            SWITCH_DOOR => return Some(Interaction::NeedsSwitch),
+            LOCKED_DOOR if !player.inventory.take(ItemKind::Key) => {
+                return Some(Interaction::Locked)
+            }
+            LOCKED_DOOR => Interaction::Unlocked,
+            _ if self.doors[index].opening => Interaction::Closed,
+            _ => Interaction::Opened,
+        };
+
+        // Las puertas dobles (celdas vecinas) se mueven juntas
+        let opening = interaction != Interaction::Closed;
+        for i in self.group(index) {
+            let door = &mut self.doors[i];
+            door.opening = opening;
+            if door.kind == LOCKED_DOOR {
+                door.kind = DOOR;
+            }
+        }
+        Some(interaction)
+    }
+
+    /// Abre todas las puertas al instante, sin animación.
+    pub fn open_all(&mut self, maze: &mut [Vec<char>]) {
+        for door in &mut self.doors {
+            door.opening = true;
+            door.open = 1.0;
+            maze[door.y][door.x] = ' ';
+        }
+    }
+
+    /// Avanza la animación un tick y actualiza las celdas del laberinto. Una
+    /// puerta no se cierra si hay alguien (`occupied`) en su celda.
+    pub fn update(&mut self, maze: &mut [Vec<char>], occupied: &[(f32, f32)]) {
+        for door in &mut self.doors {
+            let blocked = occupied
+                .iter()
+                .any(|&(x, y)| x as usize == door.x && y as usize == door.y);
+
+            if door.opening {
+                door.open = (door.open + DOOR_SPEED).min(1.0);
+                if door.open >= 1.0 {
+                    maze[door.y][door.x] = ' ';
+                }
+            } else if door.open > 0.0 && !blocked {
+                door.open = (door.open - DOOR_SPEED).max(0.0);
+                maze[door.y][door.x] = door.kind;
+            }
+        }
+    }
+
+    // Primera celda frente al jugador que no es suelo, dentro del alcance
+    fn target(&self, player: &Player, maze: &[Vec<char>]) -> Option<(usize, usize)> {
+        for step in 0..=(REACH / REACH_STEP).round() as usize {
+            let t = step as f32 * REACH_STEP;
+            let x = player.pos.x + player.dir.x * t;
+            let y = player.pos.y + player.dir.y * t;
+            if x < 0.0 || y < 0.0 {
+                return None;
+            }
+            let (x, y) = (x as usize, y as usize);
+            let cell = *maze.get(y)?.get(x)?;
+            let open_door = self.at(x, y).is_some_and(|door| door.opening);
+            if !is_floor(cell) || open_door {
+                return Some((x, y));
+            }
+        }
+        None
+    }
+
+    // La puerta `index` y las puertas vecinas del mismo tipo
+    fn group(&self, index: usize) -> Vec<usize> {
+        let mut group = vec![index];
+        let mut i = 0;
+        while i < group.len() {
+            let (x, y, kind) = {
+                let door = &self.doors[group[i]];
+                (door.x, door.y, door.kind)
+            };
+            for (j, other) in self.doors.iter().enumerate() {
+                if !group.contains(&j)
+                    && other.kind == kind
+                    && x.abs_diff(other.x) + y.abs_diff(other.y) == 1
+                {
+                    group.push(j);
+                }
+            }
+            i += 1;
+        }
+        group
+    }
+}
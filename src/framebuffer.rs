@@ -1,99 +1,356 @@
 use crate::color::Color;
-use rusttype::{point, Font, PositionedGlyph, Scale};
+use crate::text::{layout_line, line_width, wrap_lines, Align, TextStyle};
+use crate::texture::Texture;
+use rayon::prelude::*;
+
+/// Filtro con el que se escala la imagen interna al tamaño de la ventana.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Píxeles duros, para el aspecto retro a baja resolución.
+    Nearest,
+    /// Interpola los cuatro píxeles vecinos; más suave.
+    Bilinear,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Filter> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Bilinear => "bilinear",
+        }
+    }
+}
+
+/// Píxeles de la pantalla empaquetados como `0RGB` en un `u32`, el formato que
+/// recibe minifb, para entregarlos a la ventana sin conversiones.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
-    pub buffer: Vec<Color>,
-    pub background_color: Color,
-    pub current_color: Color,
+    pub buffer: Vec<u32>,
+    pub background_color: u32,
 }
 
 impl Framebuffer {
     // Constructor para crear un nuevo framebuffer con ancho y alto dados
     pub fn new(width: usize, height: usize) -> Self {
-        let buffer = vec![Color::new(0, 0, 0); width * height]; // Inicializar con color negro
         Self {
-            buffer,
+            buffer: vec![0; width * height], // Inicializar con color negro
             width,
             height,
-            background_color: Color::new(0, 0, 0), // Color negro como predeterminado
-            current_color: Color::new(255, 255, 255), // Color blanco como predeterminado
+            background_color: 0x000000, // Color negro como predeterminado
         }
     }
 
-    pub fn point_with_color(&mut self, x: usize, y: usize, color: Color) {
+    pub fn point(&mut self, x: usize, y: usize, pixel: u32) {
         if x < self.width && y < self.height {
-            self.buffer[y * self.width + x] = color;
+            self.buffer[y * self.width + x] = pixel;
         }
     }
 
-    // Métodos para settear los colores de fondo y actual
-    pub fn set_background_color(&mut self, color: impl Into<Color>) {
-        self.background_color = color.into();
+    pub fn point_with_color(&mut self, x: usize, y: usize, color: Color) {
+        self.point(x, y, color.to_hex());
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
-        self.current_color = Color::from_hex(color);
+    /// Rellena un rectángulo, recortado a los bordes de la pantalla, fila por fila.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, pixel: u32) {
+        let x_end = x.saturating_add(width).min(self.width);
+        let y_end = y.saturating_add(height).min(self.height);
+        if x >= x_end {
+            return;
+        }
+        for row in y..y_end {
+            let start = row * self.width;
+            self.buffer[start + x..start + x_end].fill(pixel);
+        }
     }
 
-    pub fn draw_rectangle(
+    /// Borde de un rectángulo de `thickness` píxeles hacia adentro.
+    pub fn draw_rect_outline(
         &mut self,
         x: usize,
         y: usize,
         width: usize,
         height: usize,
+        thickness: usize,
         color: Color,
     ) {
-        for i in 0..width {
-            for j in 0..height {
-                self.point_with_color(x + i, y + j, color.clone());
+        let pixel = color.to_hex();
+        let thickness = thickness.min(width).min(height);
+        self.fill_rect(x, y, width, thickness, pixel);
+        self.fill_rect(x, y + height - thickness, width, thickness, pixel);
+        self.fill_rect(x, y, thickness, height, pixel);
+        self.fill_rect(x + width - thickness, y, thickness, height, pixel);
+    }
+
+    /// Línea entre dos puntos con el algoritmo de Bresenham; los puntos fuera
+    /// de la pantalla se descartan.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let pixel = color.to_hex();
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+
+        loop {
+            self.plot(x, y, pixel);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
             }
         }
     }
 
+    /// Contorno de un círculo (algoritmo del punto medio).
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, radius: i32, color: Color) {
+        let pixel = color.to_hex();
+        let (mut x, mut y) = (radius, 0);
+        let mut error = 1 - radius;
+
+        while x >= y {
+            for (px, py) in [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ] {
+                self.plot(cx + px, cy + py, pixel);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Círculo relleno, dibujado como una franja horizontal por fila.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: i32, color: Color) {
+        let pixel = color.to_hex();
+        for dy in -radius..=radius {
+            let half = ((radius * radius - dy * dy) as f32).sqrt() as i32;
+            self.span(cy + dy, cx - half, cx + half, pixel);
+        }
+    }
+
+    pub fn draw_triangle(&mut self, a: (i32, i32), b: (i32, i32), c: (i32, i32), color: Color) {
+        self.draw_line(a.0, a.1, b.0, b.1, color);
+        self.draw_line(b.0, b.1, c.0, c.1, color);
+        self.draw_line(c.0, c.1, a.0, a.1, color);
+    }
+
+    /// Triángulo relleno: cada fila se cubre entre los dos bordes que la cruzan.
+    pub fn fill_triangle(&mut self, a: (i32, i32), b: (i32, i32), c: (i32, i32), color: Color) {
+        let pixel = color.to_hex();
+        let mut points = [a, b, c];
+        points.sort_by_key(|p| p.1);
+        let [top, middle, bottom] = points;
+
+        // Corte del borde p-q con la fila `y` (solo se llama con y dentro de él)
+        let edge_x = |p: (i32, i32), q: (i32, i32), y: i32| {
+            if q.1 == p.1 {
+                p.0
+            } else {
+                p.0 + (q.0 - p.0) * (y - p.1) / (q.1 - p.1)
+            }
+        };
+
+        let first = top.1.max(0);
+        let last = bottom.1.min(self.height as i32 - 1);
+        for y in first..=last {
+            let long = edge_x(top, bottom, y);
+            let short = if y < middle.1 {
+                edge_x(top, middle, y)
+            } else {
+                edge_x(middle, bottom, y)
+            };
+            self.span(y, long.min(short), long.max(short), pixel);
+        }
+    }
+
+    /// Copia una textura escalada (vecino más cercano) al rectángulo dado,
+    /// recortada a la pantalla y mezclada con opacidad `alpha` (0.0 a 1.0)
+    /// multiplicada por la de cada píxel de la textura.
+    pub fn blit_texture(
+        &mut self,
+        texture: &Texture,
+        x: i32,
+        y: i32,
+        width: usize,
+        height: usize,
+        alpha: f32,
+    ) {
+        let alpha = (alpha.clamp(0.0, 1.0) * 255.0) as u32;
+        if alpha == 0 || width == 0 || height == 0 {
+            return;
+        }
+
+        let first_x = x.max(0);
+        let last_x = (x + width as i32).min(self.width as i32);
+        let first_y = y.max(0);
+        let last_y = (y + height as i32).min(self.height as i32);
+
+        for py in first_y..last_y {
+            let ty = (py - y) as usize * texture.height as usize / height;
+            let row = py as usize * self.width;
+            for px in first_x..last_x {
+                let tx = (px - x) as usize * texture.width as usize / width;
+                // Opacidad global por la del píxel de la textura
+                let opacity = alpha * texture.get_alpha(tx, ty) as u32 / 255;
+                if opacity == 0 {
+                    continue;
+                }
+                let source = texture.get_pixel(tx, ty);
+                let index = row + px as usize;
+                self.buffer[index] = if opacity == 255 {
+                    source
+                } else {
+                    blend_colors(self.buffer[index], source, opacity)
+                };
+            }
+        }
+    }
+
+    // Punto con coordenadas con signo, ignorado si cae fuera de la pantalla
+    fn plot(&mut self, x: i32, y: i32, pixel: u32) {
+        if x >= 0 && y >= 0 {
+            self.point(x as usize, y as usize, pixel);
+        }
+    }
+
+    // Fila `y` desde `x0` hasta `x1` inclusive, recortada a la pantalla
+    fn span(&mut self, y: i32, x0: i32, x1: i32, pixel: u32) {
+        if y < 0 || y >= self.height as i32 || x1 < 0 || x0 >= self.width as i32 {
+            return;
+        }
+        let x0 = x0.max(0) as usize;
+        let x1 = x1.min(self.width as i32 - 1) as usize;
+        self.fill_rect(x0, y as usize, x1 - x0 + 1, 1, pixel);
+    }
+
     // Función para limpiar el framebuffer con el color de fondo
     pub fn clear(&mut self) {
+        self.buffer.fill(self.background_color);
+    }
+
+    // Multiplica todos los píxeles por `factor` (útil para oscurecer la escena bajo un menú)
+    pub fn dim(&mut self, factor: f32) {
         for pixel in &mut self.buffer {
-            *pixel = self.background_color.clone();
+            *pixel = (Color::from_hex(*pixel) * factor).to_hex();
         }
     }
 
-    pub fn is_point_set(&self, x: usize, y: usize) -> bool {
-        if x < self.width && y < self.height {
-            self.buffer[y * self.width + x] == Color::from_hex(0xFFFFFF)
-        } else {
-            false
+    /// Escala la imagen completa al tamaño de `target` con el filtro dado.
+    /// Cada fila de destino se calcula en un hilo distinto.
+    pub fn scale_into(&self, target: &mut Framebuffer, filter: Filter) {
+        if self.width == 0 || self.height == 0 {
+            return;
         }
-    }
 
-    pub fn to_u32_buffer(&self) -> Vec<u32> {
-        self.buffer.iter().map(|color| color.to_hex()).collect()
+        // Coordenada de origen del centro de cada píxel de destino
+        let source_x: Vec<f32> = (0..target.width)
+            .map(|x| sample_position(x, target.width, self.width))
+            .collect();
+        let target_height = target.height;
+
+        target
+            .buffer
+            .par_chunks_mut(target.width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let sy = sample_position(y, target_height, self.height);
+                match filter {
+                    Filter::Nearest => {
+                        let src = &self.buffer[sy.round() as usize * self.width..];
+                        for (pixel, &sx) in row.iter_mut().zip(&source_x) {
+                            *pixel = src[sx.round() as usize];
+                        }
+                    }
+                    Filter::Bilinear => {
+                        let y0 = sy.floor() as usize;
+                        let y1 = (y0 + 1).min(self.height - 1);
+                        let fy = sy - y0 as f32;
+                        let top = &self.buffer[y0 * self.width..(y0 + 1) * self.width];
+                        let bottom = &self.buffer[y1 * self.width..(y1 + 1) * self.width];
+                        for (pixel, &sx) in row.iter_mut().zip(&source_x) {
+                            let x0 = sx.floor() as usize;
+                            let x1 = (x0 + 1).min(self.width - 1);
+                            let fx = sx - x0 as f32;
+                            let upper = lerp_pixel(top[x0], top[x1], fx);
+                            let lower = lerp_pixel(bottom[x0], bottom[x1], fx);
+                            *pixel = lerp_pixel(upper, lower, fy);
+                        }
+                    }
+                }
+            });
     }
 
-    pub fn draw_text(&mut self, text: &str, x: usize, y: usize, color: Color) {
-        // Cargar una fuente desde los datos incrustados (por ejemplo, OpenSans)
-        let font_data = include_bytes!("../SIXTY.TTF");
-        let font = Font::try_from_bytes(font_data as &[u8]).unwrap();
+    /// Dibuja texto con el estilo dado. `x` es el borde izquierdo, el centro o
+    /// el borde derecho de cada línea según la alineación, e `y` la parte de
+    /// arriba de la primera línea. Devuelve el alto ocupado.
+    pub fn draw_text_styled(&mut self, text: &str, x: usize, y: usize, style: &TextStyle) -> usize {
+        let line_height = style.line_height();
+        let lines = wrap_lines(text, style);
 
-        let scale = Scale { x: 40.0, y: 40.0 };
+        for (i, line) in lines.iter().enumerate() {
+            let width = line_width(line, style.size) as i32;
+            let left = match style.align {
+                Align::Left => x as i32,
+                Align::Center => x as i32 - width / 2,
+                Align::Right => x as i32 - width,
+            };
+            let top = (y + i * line_height) as i32;
 
-        let v_metrics = font.v_metrics(scale);
+            if let Some(shadow) = style.shadow {
+                let offset = style.shadow_offset();
+                self.draw_glyphs(line, left + offset, top + offset, style.size, shadow);
+            }
+            self.draw_glyphs(line, left, top, style.size, style.color);
+        }
 
-        let offset = point(0.0, v_metrics.ascent);
+        lines.len() * line_height
+    }
 
-        let glyphs: Vec<PositionedGlyph> = font.layout(text, scale, offset).collect();
+    /// Tamaño de letra equivalente a `size` en la resolución de referencia
+    /// (900 píxeles de alto), para que el texto no cambie con la escala de render.
+    pub fn text_size(&self, size: f32) -> f32 {
+        size * self.height as f32 / 900.0
+    }
 
-        for glyph in glyphs {
+    fn draw_glyphs(&mut self, line: &str, x: i32, y: i32, size: f32, color: Color) {
+        for glyph in layout_line(line, size) {
             if let Some(bb) = glyph.pixel_bounding_box() {
                 glyph.draw(|gx, gy, v| {
-                    let px = x as i32 + gx as i32 + bb.min.x;
-                    let py = y as i32 + gy as i32 + bb.min.y;
+                    let px = x + gx as i32 + bb.min.x;
+                    let py = y + gy as i32 + bb.min.y;
                     if px >= 0 && px < self.width as i32 && py >= 0 && py < self.height as i32 {
                         let index = (py as usize) * self.width + (px as usize);
                         let alpha = (v * 255.0) as u32;
-                        let existing_color = self.buffer[index].to_hex();
-                        let new_color = blend_colors(existing_color, color.to_hex(), alpha);
-                        self.buffer[index] = new_color.into();
+                        self.buffer[index] =
+                            blend_colors(self.buffer[index], color.to_hex(), alpha);
                     }
                 });
             }
@@ -101,6 +358,22 @@ impl Framebuffer {
     }
 }
 
+// Posición (en píxeles de origen, entre 0 y `source - 1`) que corresponde al
+// centro del píxel `index` de una imagen de `target` píxeles
+fn sample_position(index: usize, target: usize, source: usize) -> f32 {
+    let position = (index as f32 + 0.5) * source as f32 / target as f32 - 0.5;
+    position.clamp(0.0, (source - 1) as f32)
+}
+
+fn lerp_pixel(a: u32, b: u32, t: f32) -> u32 {
+    let channel = |shift: u32| {
+        let from = ((a >> shift) & 0xFF) as f32;
+        let to = ((b >> shift) & 0xFF) as f32;
+        ((from + (to - from) * t).round() as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
 fn blend_colors(existing: u32, new: u32, alpha: u32) -> u32 {
     let existing_r = (existing >> 16) & 0xFF;
     let existing_g = (existing >> 8) & 0xFF;
@@ -116,3 +389,19 @@ fn blend_colors(existing: u32, new: u32, alpha: u32) -> u32 {
 
     (blended_r << 16) | (blended_g << 8) | blended_b
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blit_keeps_transparent_texture_pixels() {
+        // Mitad izquierda opaca y roja, mitad derecha transparente
+        let texture = Texture::from_fn(2, 1, |x, _| (0xFF0000, if x == 0 { 255 } else { 0 }));
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.buffer = vec![0x0000FF; 2];
+
+        framebuffer.blit_texture(&texture, 0, 0, 2, 1, 1.0);
+        assert_eq!(framebuffer.buffer, [0xFF0000, 0x0000FF]);
+    }
+}
@@ -1,62 +1,94 @@
+use crate::items::ItemKind;
+use crate::maze::{is_floor, thin_wall};
 use crate::player::Player;
 use gilrs::{Button, Event, EventType, Gilrs};
-use minifb::{Key, Window};
-use rodio::OutputStreamHandle; // Import the stream handle
-use rodio::{Decoder, Sink, Source};
-use std::fs::File;
-use std::io::BufReader;
-use std::sync::{Arc, Mutex};
-
-pub fn play_footstep_sound(
-    stream_handle: &OutputStreamHandle,
-    footstep_sink: &Arc<Mutex<Option<Sink>>>,
-) {
-    let mut sink_guard = footstep_sink.lock().unwrap();
-
-    // Check if the footstep sound is already playing; if so, do nothing
-    if sink_guard.is_none() {
-        // Load the footstep sound
-        let file =
-            File::open("./assets/footsteps.mp3").expect("Failed to open footstep sound file");
-        let source = Decoder::new(BufReader::new(file)).expect("Failed to decode footstep audio");
-
-        // Reduce the volume by applying an amplification factor
-        let source = source.amplify(0.8); // Adjust this value as needed
-
-        // Create a new sink to play the sound
-        let sink = Sink::try_new(stream_handle).expect("Failed to create footstep sink");
-        sink.append(source.repeat_infinite()); // Play the sound in a loop
-
-        // Start playing the sound
-        sink.play();
-
-        // Store the sink in the Arc<Mutex<Option<Sink>>>
-        *sink_guard = Some(sink);
-    }
+use minifb::{Key, KeyRepeat, MouseMode, Window};
+
+// Distancia (en celdas) a la que se detiene quien camina hacia una pared
+// delgada; mayor que un paso, así nadie la atraviesa de un salto
+const THIN_WALL_CLEARANCE: f32 = 0.1;
+
+/// Acciones de entrada de un solo tick de juego.
+///
+/// Separar la lectura del teclado/gamepad de su aplicación permite grabar
+/// cada tick en un replay y volver a alimentarlo a `process_events`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InputFrame {
+    pub forward: bool,
+    pub backward: bool,
+    pub turn_left: bool,
+    pub turn_right: bool,
+    /// Rotación producida por el ratón en radianes (ya multiplicada por la sensibilidad).
+    pub mouse_turn: f32,
+    /// Usar la puerta o el interruptor de enfrente (solo el tick en que se pulsa).
+    pub interact: bool,
+    /// Objeto del inventario que se usa este tick (1: agua, 2: batería).
+    pub use_item: Option<ItemKind>,
+    /// Encender o apagar la linterna (solo el tick en que se pulsa).
+    pub toggle_flashlight: bool,
 }
 
-pub fn stop_footstep_sound(footstep_sink: &Arc<Mutex<Option<Sink>>>) {
-    let mut sink_guard = footstep_sink.lock().unwrap();
+/// Lee el teclado, el gamepad y el ratón y los resume en un `InputFrame`.
+pub fn read_input(
+    window: &Window,
+    gilrs: &mut Gilrs,
+    last_mouse_x: &mut f32,
+    sensitivity: f32,
+) -> InputFrame {
+    let mut input = InputFrame {
+        forward: window.is_key_down(Key::Up) || window.is_key_down(Key::W),
+        backward: window.is_key_down(Key::Down) || window.is_key_down(Key::S),
+        turn_left: window.is_key_down(Key::Left) || window.is_key_down(Key::A),
+        turn_right: window.is_key_down(Key::Right) || window.is_key_down(Key::D),
+        mouse_turn: 0.0,
+        interact: window.is_key_pressed(Key::E, KeyRepeat::No),
+        use_item: if window.is_key_pressed(Key::Key1, KeyRepeat::No) {
+            Some(ItemKind::AlmondWater)
+        } else if window.is_key_pressed(Key::Key2, KeyRepeat::No) {
+            Some(ItemKind::Battery)
+        } else {
+            None
+        },
+        toggle_flashlight: window.is_key_pressed(Key::F, KeyRepeat::No),
+    };
+
+    // Handle gamepad input
+    while let Some(Event { event, .. }) = gilrs.next_event() {
+        match event {
+            EventType::ButtonPressed(Button::DPadUp, ..) => input.forward = true,
+            EventType::ButtonPressed(Button::DPadDown, ..) => input.backward = true,
+            EventType::ButtonPressed(Button::DPadLeft, ..) => input.turn_left = true,
+            EventType::ButtonPressed(Button::DPadRight, ..) => input.turn_right = true,
+            EventType::ButtonPressed(Button::South, ..) => input.interact = true,
+            EventType::ButtonPressed(Button::West, ..) => {
+                input.use_item = Some(ItemKind::AlmondWater)
+            }
+            EventType::ButtonPressed(Button::North, ..) => input.use_item = Some(ItemKind::Battery),
+            EventType::ButtonPressed(Button::RightTrigger, ..) => input.toggle_flashlight = true,
+            _ => {}
+        }
+    }
 
-    // Stop and drop the sink if it exists
-    if let Some(sink) = sink_guard.take() {
-        sink.stop(); // Stop the sound
+    // Manejar la rotación del ratón
+    if let Some((mouse_x, _)) = window.get_mouse_pos(MouseMode::Pass) {
+        input.mouse_turn = (mouse_x - *last_mouse_x) * sensitivity;
+        *last_mouse_x = mouse_x;
     }
+
+    input
 }
 
+/// Aplica un tick de entrada al jugador. Devuelve `true` si el jugador se movió
+/// o giró con las teclas (para los sonidos de pasos).
 pub fn process_events(
-    window: &Window,
+    input: &InputFrame,
     player: &mut Player,
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
     block_size: usize,
-    gilrs: &mut Gilrs,
-    stream_handle: &OutputStreamHandle, // Add the stream handle as a parameter
-    footstep_sink: &Arc<Mutex<Option<Sink>>>, // Add footstep_sink as a parameter
-) {
+) -> bool {
     let mut player_moved = false;
 
-    // Handle keyboard input
-    if window.is_key_down(Key::Up) || window.is_key_down(Key::W) {
+    if input.forward {
         let new_x = player.pos.x + player.dir.x * player.speed;
         let new_y = player.pos.y + player.dir.y * player.speed;
 
@@ -67,7 +99,7 @@ pub fn process_events(
         }
     }
 
-    if window.is_key_down(Key::Down) || window.is_key_down(Key::S) {
+    if input.backward {
         let new_x = player.pos.x - player.dir.x * player.speed;
         let new_y = player.pos.y - player.dir.y * player.speed;
 
@@ -78,103 +110,83 @@ pub fn process_events(
         }
     }
 
-    if window.is_key_down(Key::Left) || window.is_key_down(Key::A) {
-        player.angle -= player.rotation_speed;
-        if player.angle < 0.0 {
-            player.angle += 2.0 * std::f32::consts::PI;
-        }
-        update_direction(player);
+    if input.turn_left {
+        rotate_player(player, -player.rotation_speed);
         player_moved = true;
     }
 
-    if window.is_key_down(Key::Right) || window.is_key_down(Key::D) {
-        player.angle += player.rotation_speed;
-        if player.angle >= 2.0 * std::f32::consts::PI {
-            player.angle -= 2.0 * std::f32::consts::PI;
-        }
-        update_direction(player);
+    if input.turn_right {
+        rotate_player(player, player.rotation_speed);
         player_moved = true;
     }
 
-    // If the player moved, play the footstep sound
-    if player_moved {
-        play_footstep_sound(&stream_handle, &footstep_sink);
-    } else {
-        // Si el jugador no se movió, detén el sonido de los pasos
-        stop_footstep_sound(&footstep_sink);
+    if input.mouse_turn != 0.0 {
+        rotate_player(player, input.mouse_turn);
     }
 
-    // Handle gamepad input
-    while let Some(Event { event, .. }) = gilrs.next_event() {
-        match event {
-            EventType::ButtonPressed(Button::DPadUp, ..) => {
-                let new_x = player.pos.x + player.dir.x * player.speed;
-                let new_y = player.pos.y + player.dir.y * player.speed;
-                if !is_collision(new_x, new_y, maze, block_size) {
-                    player.pos.x = new_x;
-                    player.pos.y = new_y;
-                    player_moved = true;
-                }
-            }
-            EventType::ButtonPressed(Button::DPadDown, ..) => {
-                let new_x = player.pos.x - player.dir.x * player.speed;
-                let new_y = player.pos.y - player.dir.y * player.speed;
-                if !is_collision(new_x, new_y, maze, block_size) {
-                    player.pos.x = new_x;
-                    player.pos.y = new_y;
-                    player_moved = true;
-                }
-            }
-            EventType::ButtonPressed(Button::DPadLeft, ..) => {
-                player.angle -= player.rotation_speed;
-                if player.angle < 0.0 {
-                    player.angle += 2.0 * std::f32::consts::PI;
-                }
-                update_direction(player);
-                player_moved = true;
-            }
-            EventType::ButtonPressed(Button::DPadRight, ..) => {
-                player.angle += player.rotation_speed;
-                if player.angle >= 2.0 * std::f32::consts::PI {
-                    player.angle -= 2.0 * std::f32::consts::PI;
-                }
-                update_direction(player);
-                player_moved = true;
-            }
-            _ => {}
-        }
-    }
+    player_moved
+}
 
-    // If the player moved using the gamepad, play the footstep sound
-    if player_moved {
-        play_footstep_sound(&stream_handle, &footstep_sink);
-    } else {
-        // Si el jugador no se movió, detén el sonido de los pasos
-        stop_footstep_sound(&footstep_sink);
+fn rotate_player(player: &mut Player, delta: f32) {
+    player.angle += delta;
+    if player.angle < 0.0 {
+        player.angle += 2.0 * std::f32::consts::PI;
+    }
+    if player.angle >= 2.0 * std::f32::consts::PI {
+        player.angle -= 2.0 * std::f32::consts::PI;
     }
+    update_direction(player);
 }
 
-pub fn is_collision(x: f32, y: f32, maze: &Vec<Vec<char>>, block_size: usize) -> bool {
+pub fn is_collision(x: f32, y: f32, maze: &[Vec<char>], block_size: usize) -> bool {
     let maze_x = (x * block_size as f32) as usize / block_size;
     let maze_y = (y * block_size as f32) as usize / block_size;
 
-    // Verificar si los índices están dentro de los límites del laberinto
-    if maze_x >= maze[0].len() || maze_y >= maze.len() {
-        return true; // Considerar fuera de los límites como una colisión
+    // Fuera de los límites del laberinto (las filas pueden tener largos
+    // distintos) se considera una colisión
+    let Some(&cell) = maze.get(maze_y).and_then(|row| row.get(maze_x)) else {
+        return true;
+    };
+
+    // No considerar 'g' ni las llaves como una pared
+    if !is_floor(cell) && cell != 'g' {
+        return true;
     }
 
-    // No considerar 'g' como una pared
-    maze[maze_y][maze_x] != ' ' && maze[maze_y][maze_x] != 'g'
+    // Las paredes delgadas de la celda y de sus vecinas, que pueden estar en
+    // el borde compartido
+    let rows = maze_y.saturating_sub(1)..=maze_y + 1;
+    rows.flat_map(|row| (maze_x.saturating_sub(1)..=maze_x + 1).map(move |col| (col, row)))
+        .filter_map(|(col, row)| thin_wall(*maze.get(row)?.get(col)?, col, row))
+        .any(|segment| segment.distance_to((x, y)) < THIN_WALL_CLEARANCE)
 }
 
-pub fn has_won(x: f32, y: f32, maze: &Vec<Vec<char>>, block_size: usize) -> bool {
+pub fn has_won(x: f32, y: f32, maze: &[Vec<char>], block_size: usize) -> bool {
     let maze_x = (x * block_size as f32) as usize / block_size;
     let maze_y = (y * block_size as f32) as usize / block_size;
 
-    maze[maze_y][maze_x] == 'g'
+    maze.get(maze_y).and_then(|row| row.get(maze_x)) == Some(&'g')
 }
 
 pub fn update_direction(player: &mut Player) {
     player.dir.x = player.angle.cos();
     player.dir.y = player.angle.sin();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn past_the_end_of_a_short_row_is_a_collision() {
+        let maze: Vec<Vec<char>> = ["+------+", "|  |", "|      |", "+------+"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+
+        assert!(!is_collision(1.5, 1.5, &maze, 100));
+        assert!(is_collision(5.5, 1.5, &maze, 100));
+        assert!(!is_collision(5.5, 2.5, &maze, 100));
+        assert!(!has_won(5.5, 1.5, &maze, 100));
+    }
+}
@@ -0,0 +1,315 @@
+use crate::framebuffer::Framebuffer;
+use crate::lighting::Lighting;
+use crate::player::Player;
+
+// Distancia (en celdas) a la que se recoge un objeto al pasar cerca
+const PICKUP_RADIUS: f32 = 0.4;
+
+// Distancia a la que se puede recoger una nota con Interactuar
+const READ_REACH: f32 = 1.0;
+
+// Alto de un objeto respecto al de una pared
+const ITEM_SCALE: f32 = 0.35;
+
+// Textos de las notas, en el orden en que aparecen en el nivel
+const NOTES: [&str; 4] = [
+    "Day 3. The hum never stops. I marked the walls but the marks are gone.",
+    "Don't drink the water from the puddles. Only the sealed bottles.",
+    "It's faster than it looks. It can't open doors. Close them behind you.",
+    "If you are reading this, the exit is real. I saw it. Keep going.",
+];
+
+/// Tipos de objetos que se pueden recoger. En el archivo del nivel cada uno es
+/// un carácter sobre una celda de suelo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    Key,
+    AlmondWater,
+    Battery,
+    Note,
+}
+
+impl ItemKind {
+    pub const ALL: [ItemKind; 4] = [
+        ItemKind::Key,
+        ItemKind::AlmondWater,
+        ItemKind::Battery,
+        ItemKind::Note,
+    ];
+
+    pub fn from_cell(cell: char) -> Option<ItemKind> {
+        match cell {
+            'k' => Some(ItemKind::Key),
+            'w' => Some(ItemKind::AlmondWater),
+            'b' => Some(ItemKind::Battery),
+            'n' => Some(ItemKind::Note),
+            _ => None,
+        }
+    }
+
+    pub fn cell(self) -> char {
+        match self {
+            ItemKind::Key => 'k',
+            ItemKind::AlmondWater => 'w',
+            ItemKind::Battery => 'b',
+            ItemKind::Note => 'n',
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemKind::Key => "Key",
+            ItemKind::AlmondWater => "Almond water",
+            ItemKind::Battery => "Battery",
+            ItemKind::Note => "Note",
+        }
+    }
+
+    /// Color principal, para los mapas 2D.
+    pub fn color(self) -> u32 {
+        match self {
+            ItemKind::Key => 0xE8C547,
+            ItemKind::AlmondWater => 0xCFE6F2,
+            ItemKind::Battery => 0x3C3C3C,
+            ItemKind::Note => 0xEDE6D0,
+        }
+    }
+
+    /// Píxel del dibujo del objeto en (u, v), ambos entre 0 y 1 con v hacia
+    /// abajo, o `None` si es transparente.
+    pub fn pixel(self, u: f32, v: f32) -> Option<u32> {
+        let inside = |u0: f32, u1: f32, v0: f32, v1: f32| u >= u0 && u < u1 && v >= v0 && v < v1;
+        match self {
+            ItemKind::Key => {
+                let ring = ((u - 0.5).powi(2) + (v - 0.3).powi(2)).sqrt();
+                let shaft = inside(0.45, 0.55, 0.45, 0.95);
+                let teeth = inside(0.55, 0.7, 0.72, 0.79) || inside(0.55, 0.7, 0.86, 0.93);
+                ((0.09..0.2).contains(&ring) || shaft || teeth).then_some(0xE8C547)
+            }
+            ItemKind::AlmondWater => {
+                if inside(0.42, 0.58, 0.1, 0.2) {
+                    Some(0x3A7BD5)
+                } else if inside(0.44, 0.56, 0.2, 0.32) || inside(0.3, 0.7, 0.32, 1.0) {
+                    Some(if inside(0.3, 0.7, 0.55, 0.75) {
+                        0xF2F2E6
+                    } else {
+                        0xCFE6F2
+                    })
+                } else {
+                    None
+                }
+            }
+            ItemKind::Battery => {
+                if inside(0.44, 0.56, 0.22, 0.3) {
+                    Some(0xB0B0B0)
+                } else if inside(0.34, 0.66, 0.3, 1.0) {
+                    Some(if v < 0.5 { 0xD4A017 } else { 0x3C3C3C })
+                } else {
+                    None
+                }
+            }
+            ItemKind::Note => {
+                let line = [0.56, 0.66, 0.76, 0.86]
+                    .iter()
+                    .any(|&top| inside(0.32, 0.68, top, top + 0.03));
+                if line {
+                    Some(0x6B6B6B)
+                } else {
+                    inside(0.25, 0.75, 0.45, 1.0).then_some(0xEDE6D0)
+                }
+            }
+        }
+    }
+
+    /// Si se gasta al usarlo desde el inventario. La llave y las notas no se
+    /// usan así: la llave abre su puerta sola.
+    pub fn is_usable(self) -> bool {
+        matches!(self, ItemKind::AlmondWater | ItemKind::Battery)
+    }
+
+    /// Mensaje al usar el objeto desde el inventario.
+    pub fn use_message(self) -> &'static str {
+        match self {
+            ItemKind::AlmondWater => "You drink the almond water",
+            ItemKind::Battery => "You put in a fresh battery",
+            ItemKind::Key | ItemKind::Note => "You can't use that here",
+        }
+    }
+}
+
+/// Objetos que lleva el jugador, contados por tipo.
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    counts: [u32; 4],
+}
+
+impl Inventory {
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.counts[kind as usize]
+    }
+
+    pub fn add(&mut self, kind: ItemKind) {
+        self.counts[kind as usize] += 1;
+    }
+
+    /// Gasta un objeto del tipo dado. Devuelve `false` si no quedaba ninguno.
+    pub fn take(&mut self, kind: ItemKind) -> bool {
+        let count = &mut self.counts[kind as usize];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+}
+
+/// Un objeto en el suelo del nivel, en el centro de su celda.
+pub struct Item {
+    pub kind: ItemKind,
+    pub x: f32,
+    pub y: f32,
+    // Texto de `NOTES` si es una nota
+    note: Option<&'static str>,
+}
+
+/// Lo que pasó al recoger un objeto.
+pub struct Pickup {
+    pub kind: ItemKind,
+    /// El texto, si era una nota.
+    pub note: Option<&'static str>,
+}
+
+/// Objetos que quedan en el nivel.
+pub struct Items {
+    items: Vec<Item>,
+}
+
+impl Items {
+    /// Saca los objetos del laberinto: cada carácter de objeto pasa a ser
+    /// suelo y el objeto queda en el centro de su celda.
+    pub fn new(maze: &mut [Vec<char>]) -> Self {
+        let mut items = Vec::new();
+        let mut notes = 0;
+        for (y, row) in maze.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                if let Some(kind) = ItemKind::from_cell(*cell) {
+                    let note = (kind == ItemKind::Note).then(|| NOTES[notes % NOTES.len()]);
+                    notes += note.is_some() as usize;
+                    items.push(Item {
+                        kind,
+                        x: x as f32 + 0.5,
+                        y: y as f32 + 0.5,
+                        note,
+                    });
+                    *cell = ' ';
+                }
+            }
+        }
+        Items { items }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+
+    /// Recoge lo que el jugador tiene al lado: las llaves, el agua y las
+    /// baterías al pasar por encima; las notas solo con Interactuar.
+    pub fn pick_up(&mut self, player: &mut Player, interact: bool) -> Option<Pickup> {
+        let index = self.items.iter().position(|item| {
+            let distance = (item.x - player.pos.x).hypot(item.y - player.pos.y);
+            match item.kind {
+                ItemKind::Note => interact && distance < READ_REACH,
+                _ => distance < PICKUP_RADIUS,
+            }
+        })?;
+
+        let item = self.items.remove(index);
+        player.inventory.add(item.kind);
+        Some(Pickup {
+            kind: item.kind,
+            note: item.note,
+        })
+    }
+
+    /// Dibuja los objetos de lejos a cerca, apoyados en el suelo bajo la fila
+    /// `horizon`. Una columna solo se dibuja si el objeto está más cerca que
+    /// `depth`, la distancia a la pared que lo tapa en esa columna.
+    pub fn render(
+        &self,
+        framebuffer: &mut Framebuffer,
+        player: &Player,
+        depth: &[f32],
+        lighting: &Lighting,
+        horizon: f32,
+        block_size: usize,
+    ) {
+        let width = framebuffer.width as f32;
+        let height = framebuffer.height as f32;
+
+        let mut visible: Vec<(f32, &Item)> = self
+            .items
+            .iter()
+            .map(|item| ((item.x - player.pos.x).hypot(item.y - player.pos.y), item))
+            .filter(|&(distance, _)| distance > 0.2)
+            .collect();
+        visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (distance, item) in visible {
+            let angle = (item.y - player.pos.y).atan2(item.x - player.pos.x);
+            let mut angle_diff = (angle - player.angle).rem_euclid(std::f32::consts::TAU);
+            if angle_diff > std::f32::consts::PI {
+                angle_diff -= std::f32::consts::TAU;
+            }
+            if angle_diff.abs() > player.fov / 2.0 + 0.2 {
+                continue;
+            }
+
+            // Misma proyección que las paredes: a `distance` celdas una pared
+            // mide height / (2 * distance) y el suelo empieza en su base
+            let wall_height = height / (2.0 * distance);
+            let size = wall_height * ITEM_SCALE;
+            let bottom = horizon + wall_height / 2.0;
+            let top = bottom - size;
+            let center = width * (0.5 + angle_diff / player.fov);
+            let left = center - size / 2.0;
+
+            let x_start = left.max(0.0) as usize;
+            let x_end = (left + size).clamp(0.0, width) as usize;
+            let y_start = top.max(0.0) as usize;
+            let y_end = bottom.clamp(0.0, height) as usize;
+
+            for x in x_start..x_end {
+                if depth
+                    .get(x)
+                    .is_some_and(|&wall| wall < distance * block_size as f32)
+                {
+                    continue;
+                }
+                let u = (x as f32 + 0.5 - left) / size;
+                for y in y_start..y_end {
+                    let v = (y as f32 + 0.5 - top) / size;
+                    if let Some(pixel) = item.kind.pixel(u, v) {
+                        framebuffer.point(
+                            x,
+                            y,
+                            lighting.shade(pixel, (item.x, item.y), distance, x, y),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Icono de un objeto de `size` píxeles con la esquina superior izquierda en (x, y).
+pub fn draw_icon(framebuffer: &mut Framebuffer, kind: ItemKind, x: usize, y: usize, size: usize) {
+    for dy in 0..size {
+        for dx in 0..size {
+            let u = (dx as f32 + 0.5) / size as f32;
+            let v = (dy as f32 + 0.5) / size as f32;
+            if let Some(pixel) = kind.pixel(u, v) {
+                framebuffer.point(x + dx, y + dy, pixel);
+            }
+        }
+    }
+}
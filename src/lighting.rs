@@ -0,0 +1,208 @@
+use crate::lightmap::Lightmap;
+use crate::player::Player;
+
+/// Color de la niebla: casi negro, el nivel se pierde en la oscuridad.
+pub const FOG_COLOR: u32 = 0x060503;
+
+// Luz que llega a todas partes sin la linterna
+const AMBIENT: f32 = 0.16;
+
+// Densidad de la niebla por celda de distancia
+const FOG_DENSITY: f32 = 0.2;
+
+// Radio del haz respecto al alto de la pantalla y cuánto se apaga con la distancia
+const BEAM_RADIUS: f32 = 0.55;
+const BEAM_FALLOFF: f32 = 0.08;
+const BEAM_STRENGTH: f32 = 1.1;
+
+// Cuánto ilumina el haz la niebla que atraviesa (un halo tenue)
+const BEAM_HAZE: f32 = 0.12;
+const HAZE_COLOR: (f32, f32, f32) = (255.0, 236.0, 190.0);
+
+// Fracción de batería que se gasta por tick (90 segundos de luz)
+const BATTERY_DRAIN: f32 = 1.0 / (60.0 * 90.0);
+
+// Por debajo de esta carga la linterna parpadea
+const LOW_BATTERY: f32 = 0.15;
+
+/// Linterna del jugador: se enciende y apaga y gasta batería mientras está
+/// encendida.
+#[derive(Debug, Clone)]
+pub struct Flashlight {
+    pub on: bool,
+    /// Carga entre 0.0 y 1.0.
+    pub battery: f32,
+}
+
+impl Default for Flashlight {
+    fn default() -> Self {
+        Flashlight {
+            on: true,
+            battery: 1.0,
+        }
+    }
+}
+
+impl Flashlight {
+    /// Gasta la batería de un tick.
+    pub fn update(&mut self) {
+        if self.on {
+            self.battery = (self.battery - BATTERY_DRAIN).max(0.0);
+        }
+    }
+
+    /// Intensidad del haz: 0.0 apagada o sin batería, y más débil a medida
+    /// que se descarga.
+    pub fn level(&self) -> f32 {
+        if !self.on || self.battery <= 0.0 {
+            return 0.0;
+        }
+        0.4 + 0.6 * self.battery.sqrt()
+    }
+
+    /// Intensidad en el tick dado, con los parpadeos de la batería baja.
+    pub fn intensity(&self, tick: u64) -> f32 {
+        let intensity = self.level();
+        if self.battery < LOW_BATTERY && noise(tick / 4) < 1.0 - self.battery / LOW_BATTERY {
+            intensity * 0.3
+        } else {
+            intensity
+        }
+    }
+}
+
+/// Oscuridad alrededor del jugador para la cordura: 1.0 sin linterna ni luces
+/// cerca, 0.0 con la linterna a plena carga o bajo una luz del nivel.
+pub fn darkness(player: &Player, lightmap: &Lightmap, tick: u64) -> f32 {
+    let local = lightmap.light_at(player.pos.x, player.pos.y, tick);
+    let local = local.iter().sum::<f32>() / 3.0;
+    (1.0 - player.flashlight.level() - local).clamp(0.0, 1.0)
+}
+
+/// Iluminación de un cuadro: la luz ambiente, el haz de la linterna centrado
+/// en la pantalla, las luces del nivel con sus parpadeos y la niebla según la
+/// distancia.
+pub struct Lighting<'a> {
+    center_x: f32,
+    center_y: f32,
+    radius: f32,
+    beam: f32,
+    origin: (f32, f32),
+    // Dirección del rayo de cada columna de la pantalla
+    directions: Vec<(f32, f32)>,
+    lightmap: &'a Lightmap,
+    lights: Vec<[f32; 3]>,
+    tick: u64,
+}
+
+impl<'a> Lighting<'a> {
+    pub fn new(
+        width: usize,
+        height: usize,
+        player: &Player,
+        lightmap: &'a Lightmap,
+        tick: u64,
+    ) -> Self {
+        let directions = (0..width)
+            .map(|x| {
+                let a = player.angle - player.fov / 2.0 + player.fov * x as f32 / width as f32;
+                (a.cos(), a.sin())
+            })
+            .collect();
+        Lighting {
+            center_x: width as f32 / 2.0,
+            center_y: height as f32 / 2.0,
+            radius: height as f32 * BEAM_RADIUS,
+            beam: player.flashlight.intensity(tick),
+            origin: (player.pos.x, player.pos.y),
+            directions,
+            lightmap,
+            lights: lightmap.frame(tick),
+            tick,
+        }
+    }
+
+    /// Punto del nivel a `distance` celdas de la cámara por el rayo de la
+    /// columna `x`.
+    pub fn point(&self, x: usize, distance: f32) -> (f32, f32) {
+        let (dx, dy) = self.directions[x];
+        (self.origin.0 + dx * distance, self.origin.1 + dy * distance)
+    }
+
+    /// Panel de luz del techo en `point`, si hay uno, ya con la niebla.
+    pub fn panel(&self, point: (f32, f32), distance: f32) -> Option<u32> {
+        let color = self.lightmap.panel(point.0, point.1, self.tick)?;
+        let fog = 1.0 - (-FOG_DENSITY * distance).exp();
+        let channel = |shift: u32| {
+            let value = ((color >> shift) & 0xFF) as f32;
+            let fog_color = ((FOG_COLOR >> shift) & 0xFF) as f32;
+            (value * (1.0 - fog) + fog_color * fog) as u32
+        };
+        Some((channel(16) << 16) | (channel(8) << 8) | channel(0))
+    }
+
+    // Luz del haz en el píxel (x, y) de la pantalla, sin la distancia
+    fn cone(&self, x: usize, y: usize) -> f32 {
+        if self.beam <= 0.0 {
+            return 0.0;
+        }
+        let dx = x as f32 - self.center_x;
+        let dy = y as f32 - self.center_y;
+        let r = (dx * dx + dy * dy).sqrt() / self.radius;
+        let edge = (1.0 - r).clamp(0.0, 1.0);
+        self.beam * edge * edge * (3.0 - 2.0 * edge)
+    }
+
+    /// Color final de un píxel que está en `point` del nivel, a `distance`
+    /// celdas de la cámara, dibujado en (x, y) de la pantalla.
+    pub fn shade(&self, pixel: u32, point: (f32, f32), distance: f32, x: usize, y: usize) -> u32 {
+        let cone = self.cone(x, y);
+        let brightness =
+            AMBIENT + cone * BEAM_STRENGTH / (1.0 + BEAM_FALLOFF * distance * distance);
+        let local = self.lightmap.sample(&self.lights, point.0, point.1);
+        let fog = 1.0 - (-FOG_DENSITY * distance).exp();
+        let haze = cone * BEAM_HAZE * fog;
+
+        let channel = |shift: u32, haze_color: f32, local: f32| {
+            let color = ((pixel >> shift) & 0xFF) as f32 * (brightness + local);
+            let fog_color = ((FOG_COLOR >> shift) & 0xFF) as f32;
+            let value = color * (1.0 - fog) + fog_color * fog + haze_color * haze;
+            value.min(255.0) as u32
+        };
+        (channel(16, HAZE_COLOR.0, local[0]) << 16)
+            | (channel(8, HAZE_COLOR.1, local[1]) << 8)
+            | channel(0, HAZE_COLOR.2, local[2])
+    }
+}
+
+/// Valor pseudoaleatorio entre 0 y 1 fijo para cada `n`, para que los
+/// parpadeos se repitan igual en un replay.
+pub fn noise(n: u64) -> f32 {
+    let x = (n as u32).wrapping_mul(0x9E37_79B1) ^ 0x5BD1_E995;
+    let x = x.wrapping_mul(0x2C1B_3C6D) ^ (x >> 15);
+    (x >> 8) as f32 / (1u32 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_lights_ease_the_darkness() {
+        let mut maze: Vec<Vec<char>> = ["+--------+", "|F       |", "+--------+"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+        let lightmap = Lightmap::new(&mut maze, &[]);
+        let mut player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+        player.flashlight.on = false;
+
+        let lit = darkness(&player, &lightmap, 0);
+        player.pos.x = 8.5;
+        assert_eq!(darkness(&player, &lightmap, 0), 1.0);
+        assert!(lit < 1.0);
+
+        player.flashlight.on = true;
+        assert_eq!(darkness(&player, &lightmap, 0), 0.0);
+    }
+}
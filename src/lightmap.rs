@@ -0,0 +1,313 @@
+use crate::doors::is_door;
+use crate::lighting::noise;
+use crate::maze::{is_floor, is_transparent, thin_wall, wall_span, HALF_WALL, PILLAR};
+
+/// Panel fluorescente en el techo de una celda.
+pub const FLUORESCENT: char = 'F';
+/// Cartel de salida colgado del techo de una celda.
+pub const EXIT_SIGN: char = 'E';
+
+// Muestras del lightmap por lado de celda
+const SAMPLES: usize = 4;
+
+// Paso (en celdas) al recorrer la línea entre una luz y una muestra
+const OCCLUSION_STEP: f32 = 0.1;
+
+// Grupos de parpadeo: el 0 no parpadea y las luces de cada uno de los demás
+// parpadean a la vez
+const FLICKER_GROUPS: usize = 4;
+
+// Ticks que dura cada estado de un parpadeo
+const FLICKER_TICKS: u64 = 3;
+
+// Lado del panel dibujado en el techo, en celdas
+const PANEL_SIZE: f32 = 0.6;
+
+/// Una fuente de luz: su color, hasta dónde llega (en celdas) y si parpadea.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub color: u32,
+    pub radius: f32,
+    pub flicker: bool,
+}
+
+impl LightSource {
+    /// Luces que entiende cualquier nivel sin definirlas.
+    pub fn default_for(cell: char) -> Option<LightSource> {
+        match cell {
+            FLUORESCENT => Some(LightSource {
+                color: 0xFFF2D0,
+                radius: 4.0,
+                flicker: true,
+            }),
+            EXIT_SIGN => Some(LightSource {
+                color: 0x30E060,
+                radius: 2.5,
+                flicker: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Lee una línea `light <carácter> <rrggbb> <radio> [flicker]` de un
+    /// archivo de nivel, que define (o redefine) el carácter como una luz.
+    pub fn parse(line: &str) -> Result<(char, LightSource), String> {
+        let mut parts = line.split_whitespace().skip(1);
+        let mut next = |what: &str| parts.next().ok_or_else(|| format!("missing {}", what));
+
+        let mut cell = next("cell")?.chars();
+        let (Some(cell), None) = (cell.next(), cell.next()) else {
+            return Err("the cell must be a single character".to_string());
+        };
+        if is_floor(cell)
+            || is_door(cell)
+            || "+-|gS".contains(cell)
+            || [PILLAR, HALF_WALL].contains(&cell)
+            || is_transparent(cell)
+        {
+            return Err(format!("`{}` is already used by the maze", cell));
+        }
+        let color = next("color")?;
+        let color = u32::from_str_radix(color.trim_start_matches('#'), 16)
+            .map_err(|_| format!("invalid color `{}`", color))?;
+        let radius = next("radius")?;
+        let radius = match radius.parse::<f32>() {
+            Ok(radius) if radius > 0.0 => radius,
+            _ => return Err(format!("invalid radius `{}`", radius)),
+        };
+        let flicker = match parts.next() {
+            None | Some("steady") => false,
+            Some("flicker") => true,
+            Some(other) => return Err(format!("expected `flicker` or `steady`, got `{}`", other)),
+        };
+
+        Ok((
+            cell,
+            LightSource {
+                color,
+                radius,
+                flicker,
+            },
+        ))
+    }
+}
+
+/// Una luz del nivel, en el centro de su celda.
+pub struct Light {
+    pub x: f32,
+    pub y: f32,
+    pub source: LightSource,
+    group: usize,
+}
+
+/// Luz de las fuentes del nivel, calculada una vez al cargarlo.
+///
+/// Cada celda se divide en `SAMPLES` x `SAMPLES` muestras y cada muestra
+/// guarda la luz RGB que le llega de las luces que la ven sin paredes de por
+/// medio (las puertas no tapan la luz). Las luces que parpadean se guardan en
+/// capas aparte para poder atenuarlas en cada cuadro sin recalcular nada.
+pub struct Lightmap {
+    lights: Vec<Light>,
+    // Índice de la luz de cada celda, para dibujar su panel
+    cells: Vec<Vec<Option<usize>>>,
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<[f32; 3]>>,
+}
+
+impl Lightmap {
+    /// Saca las luces del laberinto, dejando suelo en su lugar, y hornea el
+    /// lightmap. `palette` son las luces definidas por el nivel, que tienen
+    /// prioridad sobre las de siempre.
+    pub fn new(maze: &mut [Vec<char>], palette: &[(char, LightSource)]) -> Self {
+        let mut lights = Vec::new();
+        let mut cells: Vec<Vec<Option<usize>>> =
+            maze.iter().map(|row| vec![None; row.len()]).collect();
+        for (y, row) in maze.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let source = palette
+                    .iter()
+                    .find(|&&(c, _)| c == *cell)
+                    .map(|&(_, source)| source)
+                    .or_else(|| LightSource::default_for(*cell));
+                if let Some(source) = source {
+                    let group = if source.flicker {
+                        1 + (x * 7 + y * 13) % (FLICKER_GROUPS - 1)
+                    } else {
+                        0
+                    };
+                    cells[y][x] = Some(lights.len());
+                    lights.push(Light {
+                        x: x as f32 + 0.5,
+                        y: y as f32 + 0.5,
+                        source,
+                        group,
+                    });
+                    *cell = ' ';
+                }
+            }
+        }
+
+        let width = maze.iter().map(Vec::len).max().unwrap_or(0) * SAMPLES;
+        let height = maze.len() * SAMPLES;
+        let mut layers = vec![vec![[0.0; 3]; width * height]; FLICKER_GROUPS];
+        for light in &lights {
+            bake(maze, light, width, height, &mut layers[light.group]);
+        }
+
+        Lightmap {
+            lights,
+            cells,
+            width,
+            height,
+            layers,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Light> {
+        self.lights.iter()
+    }
+
+    /// Luz de cada muestra en el tick dado, con los parpadeos aplicados.
+    pub fn frame(&self, tick: u64) -> Vec<[f32; 3]> {
+        let factors: Vec<f32> = (0..FLICKER_GROUPS).map(|g| flicker(g, tick)).collect();
+        let mut frame = self.layers[0].clone();
+        for (layer, &factor) in self.layers.iter().zip(&factors).skip(1) {
+            for (sample, light) in frame.iter_mut().zip(layer) {
+                for (value, light) in sample.iter_mut().zip(light) {
+                    *value += light * factor;
+                }
+            }
+        }
+        frame
+    }
+
+    /// Luz en el punto (x, y) del nivel, interpolada entre las muestras de
+    /// `frame`.
+    pub fn sample(&self, frame: &[[f32; 3]], x: f32, y: f32) -> [f32; 3] {
+        if self.lights.is_empty() {
+            return [0.0; 3];
+        }
+        let fx = (x * SAMPLES as f32 - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let fy = (y * SAMPLES as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (fx as usize, fy as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+        let at = |x: usize, y: usize| frame[y * self.width + x];
+        let (a, b, c, d) = (at(x0, y0), at(x1, y0), at(x0, y1), at(x1, y1));
+        std::array::from_fn(|i| {
+            let top = a[i] + (b[i] - a[i]) * tx;
+            let bottom = c[i] + (d[i] - c[i]) * tx;
+            top + (bottom - top) * ty
+        })
+    }
+
+    /// Luz en el punto (x, y) del nivel en el tick dado, sin armar el cuadro
+    /// entero.
+    pub fn light_at(&self, x: f32, y: f32, tick: u64) -> [f32; 3] {
+        let mut light = [0.0; 3];
+        for (group, layer) in self.layers.iter().enumerate() {
+            let factor = if group == 0 {
+                1.0
+            } else {
+                flicker(group, tick)
+            };
+            for (value, sample) in light.iter_mut().zip(self.sample(layer, x, y)) {
+                *value += sample * factor;
+            }
+        }
+        light
+    }
+
+    /// Color del panel de la luz en el techo sobre (x, y), si hay uno, ya
+    /// atenuado por su parpadeo.
+    pub fn panel(&self, x: f32, y: f32, tick: u64) -> Option<u32> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let index = (*self.cells.get(y as usize)?.get(x as usize)?)?;
+        let light = &self.lights[index];
+        let half = PANEL_SIZE / 2.0;
+        if (x - light.x).abs() > half || (y - light.y).abs() > half {
+            return None;
+        }
+
+        let factor = 0.2 + 0.8 * flicker(light.group, tick);
+        let channel =
+            |shift: u32| ((((light.source.color >> shift) & 0xFF) as f32) * factor) as u32;
+        Some((channel(16) << 16) | (channel(8) << 8) | channel(0))
+    }
+}
+
+// Suma la luz de `light` a las muestras que alcanza y ve
+fn bake(maze: &[Vec<char>], light: &Light, width: usize, height: usize, layer: &mut [[f32; 3]]) {
+    let radius = light.source.radius;
+    let color = [16, 8, 0].map(|shift| ((light.source.color >> shift) & 0xFF) as f32 / 255.0);
+    let first = |center: f32| ((center - radius) * SAMPLES as f32).max(0.0) as usize;
+    let last =
+        |center: f32, size: usize| (((center + radius) * SAMPLES as f32).ceil() as usize).min(size);
+
+    for sy in first(light.y)..last(light.y, height) {
+        for sx in first(light.x)..last(light.x, width) {
+            let x = (sx as f32 + 0.5) / SAMPLES as f32;
+            let y = (sy as f32 + 0.5) / SAMPLES as f32;
+            let distance = (x - light.x).hypot(y - light.y);
+            if distance >= radius || !visible(maze, (light.x, light.y), (x, y)) {
+                continue;
+            }
+            let falloff = (1.0 - distance / radius).powi(2);
+            for (sample, channel) in layer[sy * width + sx].iter_mut().zip(color) {
+                *sample += channel * falloff;
+            }
+        }
+    }
+}
+
+// La luz atraviesa el suelo, las paredes bajas, los vidrios y las rejas, y
+// las puertas, que al hornear están cerradas pero se abren durante la partida
+fn passes_light(maze: &[Vec<char>], x: f32, y: f32) -> bool {
+    maze.get(y as usize)
+        .and_then(|row| row.get(x as usize))
+        .is_some_and(|&cell| {
+            is_door(cell)
+                || is_transparent(cell)
+                || wall_span(cell).is_none_or(|span| !span.covers(1.0))
+        })
+}
+
+// Si la línea entre `from` y `to` cruza la pared delgada de la celda en (x, y)
+fn crosses_thin_wall(maze: &[Vec<char>], x: f32, y: f32, from: (f32, f32), to: (f32, f32)) -> bool {
+    let (x, y) = (x as usize, y as usize);
+    maze.get(y)
+        .and_then(|row| row.get(x))
+        .and_then(|&cell| thin_wall(cell, x, y))
+        .is_some_and(|segment| segment.crosses(from, to))
+}
+
+// Recorre la línea entre `from` y `to` buscando una pared en medio
+fn visible(maze: &[Vec<char>], from: (f32, f32), to: (f32, f32)) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = ((dx.hypot(dy) / OCCLUSION_STEP).ceil() as usize).max(1);
+    (0..=steps).all(|i| {
+        let t = i as f32 / steps as f32;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        passes_light(maze, x, y) && !crosses_thin_wall(maze, x, y, from, to)
+    })
+}
+
+// Intensidad de un grupo de luces en el tick dado: casi siempre encendidas,
+// con cortes y bajones breves como un tubo fluorescente viejo
+fn flicker(group: usize, tick: u64) -> f32 {
+    if group == 0 {
+        return 1.0;
+    }
+    let n = noise((tick / FLICKER_TICKS) * FLICKER_GROUPS as u64 + group as u64);
+    if n < 0.04 {
+        0.1
+    } else if n < 0.1 {
+        0.55
+    } else {
+        1.0
+    }
+}
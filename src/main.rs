@@ -3,10 +3,8 @@ use gilrs::Gilrs;
 mod sprite;
 use sprite::Sprite;
 
-extern crate rodio;
-
-use once_cell::sync::Lazy;
-use std::sync::{Arc, Mutex};
+use once_cell::sync::{Lazy, OnceCell};
+use std::sync::Arc;
 
 mod timer;
 use timer::Timer;
@@ -15,53 +13,102 @@ mod color;
 use crate::color::Color;
 
 mod cast_ray;
-use cast_ray::cast_rays;
 
 mod framebuffer;
-use framebuffer::Framebuffer;
+use framebuffer::{Filter, Framebuffer};
+
+mod text;
+use text::{Align, TextStyle};
 
 mod input;
-use input::{has_won, process_events, update_direction};
+use input::{has_won, process_events, read_input, InputFrame};
+
+use minifb::{Key, MouseMode, ScaleMode, Window, WindowOptions};
+
+mod replay;
+use replay::{Playback, Replay};
+
+mod menu;
+use menu::{move_selection, read_menu_action, render_menu, MenuAction, PAUSE_ITEMS};
 
-use minifb::{Key, MouseMode, Window, WindowOptions};
+mod settings;
+use settings::{Settings, SETTINGS_PATH};
 
 mod player;
 use player::Player;
 
 mod maze;
-use maze::load_maze;
+use maze::load_level;
+
+mod doors;
+use doors::{Doors, Interaction};
+
+mod items;
+use items::{draw_icon, ItemKind, Items};
+
+mod vitals;
+
+mod lighting;
+
+mod camera;
+
+mod lightmap;
+use lightmap::Lightmap;
+
+mod cli;
+use cli::{parse_args, Difficulty, USAGE};
 
 mod texture;
 use texture::Texture;
 
-use rodio::{source::Source, Decoder, OutputStream, Sink};
-use std::fs::File;
-use std::io::BufReader;
-use std::thread;
+mod audio;
+use audio::{AudioManager, Sound};
+
+mod postfx;
+use postfx::PostEffects;
+
+mod minimap;
+use minimap::Minimap;
+
+mod sky;
+
+mod render;
+use render::{render3d, render_top_down, timed, Scene, StageTimes};
+
+mod bench;
+
+use std::time::{SystemTime, UNIX_EPOCH};
 
-static WALL1: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/WALL.jpg")));
-static START_SCREEN: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/start.jpg")));
-static LOST_SCREEN: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/lost_backrooms.jpg")));
+// Ticks que se muestra un mensaje corto del HUD (2 segundos); los largos
+// duran más para que dé tiempo a leerlos
+const MESSAGE_TICKS: usize = 120;
+const MESSAGE_TICKS_PER_CHAR: usize = 4;
 
+// Directorio de assets elegido por línea de comandos (antes de cargar texturas)
+static ASSET_DIR: OnceCell<String> = OnceCell::new();
+
+static START_SCREEN: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(load_texture("start.jpg")));
+static LOST_SCREEN: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(load_texture("lost_backrooms.jpg")));
+
+fn asset_path(name: &str) -> String {
+    let dir = ASSET_DIR.get().map(String::as_str).unwrap_or("./assets");
+    format!("{}/{}", dir, name)
+}
+
+// Textura de las que se cargan una sola vez; `main` las carga al empezar para
+// que un directorio de assets equivocado se informe antes de abrir la ventana
+fn load_texture(name: &str) -> Texture {
+    Texture::new(&asset_path(name)).unwrap_or_else(|e| exit_with_usage(&e))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum GameState {
     StartScreen,
     Playing,
     Won,
     Lost, // Estado agregado
-}
-
-fn draw_cell(framebuffer: &mut Framebuffer, x: usize, y: usize, block_size: usize, cell: char) {
-    let color = match cell {
-        '+' => Color::from_hex(0xD6C34E),
-        '-' => Color::from_hex(0xBFAA25),
-        '|' => Color::from_hex(0xB9AB53),
-        'g' => Color::from_hex(0xc92828),
-        ' ' => Color::from_hex(0x7F5A1B),
-        _ => Color::from_hex(0x000000),
-    };
-
-    framebuffer.draw_rectangle(x, y, block_size, block_size, color);
+    Paused,
+    Settings,
 }
 
 fn render_start_screen(framebuffer: &mut Framebuffer) {
@@ -69,244 +116,492 @@ fn render_start_screen(framebuffer: &mut Framebuffer) {
     let height = framebuffer.height;
 
     // Draw the start screen image
-    for y in 0..height {
-        for x in 0..width {
-            let tx = x * START_SCREEN.width as usize / width;
-            let ty = y * START_SCREEN.height as usize / height;
-            let color = START_SCREEN.get_pixel(tx, ty);
-            framebuffer.point_with_color(x, y, color);
-        }
-    }
+    framebuffer.blit_texture(&START_SCREEN, 0, 0, width, height, 1.0);
 
     // Draw the "Press any key to start" text
-    framebuffer.draw_text(
+    let style = TextStyle::new(framebuffer.text_size(40.0), Color::from_hex(0xFFFFFF))
+        .align(Align::Center)
+        .shadow(Color::from_hex(0x000000));
+    framebuffer.draw_text_styled(
         "Press any key to start",
-        width / 2 - 150,
-        height - 100,
-        Color::from_hex(0xFFFFFF),
+        width / 2,
+        height - style.line_height() * 5 / 2,
+        &style,
     );
 }
 
-fn render3d(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
-    maze: &Vec<Vec<char>>,
-    sprite: &Sprite,
-) {
-    let block_size = 100;
-    let num_rays = framebuffer.width;
+fn render_lost_screen(framebuffer: &mut Framebuffer) {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
 
-    let hh = framebuffer.height as f32 / 2.0;
-    let distance_to_projection_plane = 100.0;
+    framebuffer.blit_texture(&LOST_SCREEN, 0, 0, width, height, 1.0);
 
-    let ceiling_color = Color::from_hex(0x88814a);
-    let floor_color = Color::from_hex(0x58450e);
+    let style = TextStyle::new(framebuffer.text_size(40.0), Color::from_hex(0xFFFFFF))
+        .align(Align::Center)
+        .shadow(Color::from_hex(0x000000));
+    framebuffer.draw_text_styled(
+        "You lost! Press any key to return to start.",
+        width / 2,
+        height - style.line_height() * 3 / 2,
+        &style,
+    );
+}
 
-    for y in 0..(framebuffer.height / 2) {
-        for x in 0..framebuffer.width {
-            framebuffer.point_with_color(x, y, ceiling_color.clone());
-        }
+// La vista de la partida: en primera persona o cenital (Tab)
+fn render_scene(
+    framebuffer: &mut Framebuffer,
+    top_down: bool,
+    scene: &Scene,
+    effects: &PostEffects,
+    times: &mut StageTimes,
+) {
+    if top_down {
+        render_top_down(framebuffer, scene);
+    } else {
+        render3d(framebuffer, scene, times);
+        timed(&mut times.post, || {
+            postfx::apply(framebuffer, effects, scene.tick)
+        });
     }
+}
 
-    for y in (framebuffer.height / 2)..framebuffer.height {
-        for x in 0..framebuffer.width {
-            framebuffer.point_with_color(x, y, floor_color.clone());
+// Salud, cordura, batería e inventario abajo a la izquierda y el último mensaje abajo al centro
+fn render_hud(framebuffer: &mut Framebuffer, player: &Player, message: Option<&str>) {
+    let style = TextStyle::new(framebuffer.text_size(32.0), Color::from_hex(0xFFFFFF))
+        .shadow(Color::from_hex(0x000000));
+    let icon = style.line_height();
+    let y = framebuffer.height - icon * 3 / 2;
+
+    let bar_width = icon * 6;
+    let bar_height = (icon / 4).max(2);
+    let battery_color = if player.flashlight.on {
+        0xE8C547
+    } else {
+        0x6E6128
+    };
+    let bars = [
+        (player.flashlight.battery, battery_color),
+        (player.sanity / vitals::MAX_SANITY, 0x5F88CC),
+        (player.health / vitals::MAX_HEALTH, 0xC0392B),
+    ];
+    for (i, (fraction, color)) in bars.into_iter().enumerate() {
+        let bar_y = y - (i + 1) * bar_height * 2;
+        framebuffer.fill_rect(icon / 2, bar_y, bar_width, bar_height, 0x1A1505);
+        let filled = (bar_width as f32 * fraction.clamp(0.0, 1.0)) as usize;
+        framebuffer.fill_rect(icon / 2, bar_y, filled, bar_height, color);
+    }
+
+    let mut x = icon / 2;
+    for kind in ItemKind::ALL {
+        let count = player.inventory.count(kind);
+        if count == 0 {
+            continue;
         }
+        draw_icon(framebuffer, kind, x, y, icon);
+        x += icon;
+        let count = count.to_string();
+        framebuffer.draw_text_styled(&count, x, y, &style);
+        x += text::line_width(&count, style.size) + icon / 2;
     }
 
-    for i in 0..num_rays {
-        let current_ray = i as f32 / num_rays as f32;
-        let a = player.angle - (player.fov / 2.0) + (player.fov * current_ray);
-        let intersect = cast_rays(framebuffer, maze, player, a, block_size, false);
+    if let Some(message) = message {
+        let style = style.align(Align::Center).wrap(framebuffer.width / 2);
+        let (_, height) = text::measure_text(message, &style);
+        let top = framebuffer.height.saturating_sub(height + icon * 2);
+        framebuffer.draw_text_styled(message, framebuffer.width / 2, top, &style);
+    }
+}
 
-        let distance_to_wall = intersect.distance;
+/// Todo lo que cambia en una partida: el jugador, el monstruo y el nivel con
+/// sus puertas, objetos y luces.
+type Run = (Player, Sprite, Vec<Vec<char>>, Doors, Items, Lightmap);
+
+/// Prepara una partida nueva: el jugador y el monstruo en sus posiciones
+/// iniciales y el nivel cargado (o generado con `seed`) con sus puertas,
+/// objetos y luces. Falla si el nivel no se puede cargar.
+fn new_run(level: &str, seed: u64, difficulty: Difficulty, fov: f32) -> Result<Run, String> {
+    let player = Player::new(1.5, 1.5, fov, 0.02, 0.1);
+    let sprite = Sprite::new(
+        &asset_path("sprite.png"),
+        1.5,
+        3.5,
+        1.0,
+        difficulty.sprite_speed(),
+    )?;
+    let (mut maze, palette) = load_level(level, seed)?;
+    let items = Items::new(&mut maze);
+    let lightmap = Lightmap::new(&mut maze, &palette);
+    let doors = Doors::new(&maze);
+    Ok((player, sprite, maze, doors, items, lightmap))
+}
 
-        if distance_to_wall > 0.0 {
-            let stake_height = (hh / distance_to_wall) * distance_to_projection_plane;
-            let stake_top = (hh - (stake_height / 2.0)) as usize;
-            let stake_bottom = (hh + (stake_height / 2.0)) as usize;
+/// Termina el programa con un error en los argumentos o en los archivos que
+/// nombran, mostrando la ayuda.
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
 
-            for y in stake_top..stake_bottom {
-                let tx = intersect.tx;
-                let ty = ((y as f32 - stake_top as f32) / (stake_bottom as f32 - stake_top as f32))
-                    * 512.0;
-                let wall_color = WALL1.get_pixel(tx, ty as usize);
-                framebuffer.point_with_color(i, y, wall_color);
-            }
-        }
+/// Avanza la simulación un tick con la entrada dada. Devuelve si el jugador se
+/// movió, si la partida terminó el nuevo estado, y el mensaje para el HUD si
+/// recogió o usó algo.
+#[allow(clippy::too_many_arguments)]
+fn simulate_tick(
+    input: &InputFrame,
+    player: &mut Player,
+    sprite: &mut Sprite,
+    maze: &mut [Vec<char>],
+    doors: &mut Doors,
+    items: &mut Items,
+    lightmap: &Lightmap,
+    tick: u64,
+    block_size: usize,
+) -> (bool, Option<GameState>, Option<String>) {
+    let (x, y) = (player.pos.x, player.pos.y);
+    let player_moved = process_events(input, player, maze, block_size);
+    let walked = (player.pos.x - x).hypot(player.pos.y - y);
+    player.camera.update(walked, player.speed);
+    if input.toggle_flashlight {
+        player.flashlight.on = !player.flashlight.on;
+    }
+    player.flashlight.update();
+
+    // Una nota al alcance se lee antes que usar la puerta de enfrente
+    let mut message = match items.pick_up(player, input.interact) {
+        Some(pickup) => Some(match pickup.note {
+            Some(note) => format!("\"{}\"", note),
+            None => format!("Picked up: {}", pickup.kind.name()),
+        }),
+        None if input.interact => doors
+            .interact(player, maze)
+            .and_then(Interaction::message)
+            .map(str::to_string),
+        None => None,
+    };
+    if let Some(kind) = input.use_item {
+        message = Some(vitals::use_item(player, kind));
     }
+    doors.update(maze, &[(player.pos.x, player.pos.y), (sprite.x, sprite.y)]);
 
-    // Renderizar el sprite
-    sprite.render(framebuffer, player);
+    // Mueve el sprite hacia el jugador
+    sprite.move_towards_player(player, maze, block_size);
 
-    // Renderizar el mini-mapa
-    let mini_map_scale = 8;
-    let mini_map_size = block_size / mini_map_scale;
-    let mini_map_x_offset = framebuffer.width - maze[0].len() * mini_map_size - 10;
-    let mini_map_y_offset = framebuffer.height - maze.len() * mini_map_size - 10;
+    // Verificar si el jugador ha ganado
+    if has_won(player.pos.x, player.pos.y, maze, block_size) {
+        return (player_moved, Some(GameState::Won), message);
+    }
 
-    render2d_mini_map(
-        framebuffer,
+    // El contacto con el sprite quita salud; la cordura baja cerca de él y a
+    // oscuras
+    let health = player.health;
+    vitals::contact(player, sprite, maze, block_size);
+    if player.health < health {
+        player.camera.shake(camera::HIT_SHAKE);
+    }
+    player
+        .camera
+        .set_spotted(sprite.sees_player(player, maze, doors, block_size));
+    let darkness = lighting::darkness(player, lightmap, tick);
+    vitals::update_sanity(
         player,
-        maze,
-        mini_map_size,
-        mini_map_x_offset,
-        mini_map_y_offset,
+        sprite.threat(player, maze, doors, block_size),
+        darkness,
     );
-}
-
-fn render2d_mini_map(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
-    maze: &Vec<Vec<char>>,
-    block_size: usize,
-    x_offset: usize,
-    y_offset: usize,
-) {
-    for row in 0..maze.len() {
-        for col in 0..maze[row].len() {
-            draw_cell(
-                framebuffer,
-                x_offset + col * block_size,
-                y_offset + row * block_size,
-                block_size,
-                maze[row][col],
-            );
-        }
+    if vitals::is_dead(player) {
+        return (player_moved, Some(GameState::Lost), message);
     }
 
-    // Draw the player on the mini-map
-    framebuffer.draw_rectangle(
-        x_offset + (player.pos.x * block_size as f32) as usize,
-        y_offset + (player.pos.y * block_size as f32) as usize,
-        block_size / 2,
-        block_size / 2,
-        Color::from_hex(0x5F88CC),
-    );
+    (player_moved, None, message)
 }
 
-fn render_lost_screen(framebuffer: &mut Framebuffer) {
-    let width = framebuffer.width;
-    let height = framebuffer.height;
-
-    for y in 0..height {
-        for x in 0..width {
-            let tx = x * LOST_SCREEN.width as usize / width;
-            let ty = y * LOST_SCREEN.height as usize / height;
-            let color = LOST_SCREEN.get_pixel(tx, ty);
-            framebuffer.point_with_color(x, y, color);
+/// Reproduce un replay sin ventana ni audio e imprime el resultado de la partida.
+fn run_headless_replay(replay: Replay) {
+    let block_size = 100;
+    let (mut player, mut sprite, mut maze, mut doors, mut items, lightmap) = new_run(
+        &replay.level,
+        replay.seed,
+        replay.difficulty,
+        std::f32::consts::PI / 3.0,
+    )
+    .unwrap_or_else(|e| exit_with_usage(&e));
+
+    let mut outcome = None;
+    let mut ticks = 0;
+    for input in &replay.frames {
+        let tick = ticks;
+        ticks += 1;
+        let (_, state, _) = simulate_tick(
+            input,
+            &mut player,
+            &mut sprite,
+            &mut maze,
+            &mut doors,
+            &mut items,
+            &lightmap,
+            tick,
+            block_size,
+        );
+        if state.is_some() {
+            outcome = state;
+            break;
         }
     }
 
-    framebuffer.draw_text(
-        "You lost! Press any key to return to start.",
-        width / 2 - 150,
-        height - 50,
-        Color::from_hex(0xFFFFFF),
+    println!("seed: {}", replay.seed);
+    println!("level: {}", replay.level);
+    println!("difficulty: {}", replay.difficulty.name());
+    println!("ticks: {}/{}", ticks, replay.frames.len());
+    match outcome {
+        Some(state) => println!("outcome: {:?}", state),
+        None => println!("outcome: unfinished"),
+    }
+    println!(
+        "player: ({}, {}) angle {}",
+        player.pos.x, player.pos.y, player.angle
     );
+    println!("sprite: ({}, {})", sprite.x, sprite.y);
+    println!("health: {} sanity: {}", player.health, player.sanity);
 }
 
-fn play_background_music(sink: Arc<Mutex<Sink>>, stream_handle: rodio::OutputStreamHandle) {
-    let file = File::open("./assets/horror.mp3").expect("Failed to open music file");
-    let source = Decoder::new(BufReader::new(file)).expect("Failed to decode audio");
+fn new_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-    let amplified_source = source.amplify(0.3);
+// El framebuffer interno se reduce con la escala de resolución; `present` lo
+// escala al tamaño de la ventana. Nunca mide menos de 1x1, porque el render
+// reparte el buffer por filas.
+fn scaled_size(width: usize, height: usize, settings: &Settings) -> (usize, usize) {
+    let scale = |size: usize| ((size as f32 * settings.resolution_scale) as usize).max(1);
+    (scale(width), scale(height))
+}
 
-    let sink = sink.lock().unwrap();
-    sink.append(amplified_source.repeat_infinite());
-    sink.play();
+fn new_scaled_framebuffer(width: usize, height: usize, settings: &Settings) -> Framebuffer {
+    let (width, height) = scaled_size(width, height, settings);
+    Framebuffer::new(width, height)
 }
 
-fn play_sound_effect(
-    file_path: &str,
-    bg_music_sink: Arc<Mutex<Sink>>,
-    stream_handle: rodio::OutputStreamHandle,
+// Muestra el framebuffer en la ventana. Si no mide lo mismo que la ventana se
+// escala antes a `screen` con el filtro elegido, en vez de dejar que minifb lo estire.
+fn present(
+    window: &mut Window,
+    framebuffer: &Framebuffer,
+    screen: &mut Framebuffer,
+    filter: Filter,
 ) {
-    let file = File::open(file_path).expect("Failed to open sound effect file");
-    let source = Decoder::new(BufReader::new(file)).expect("Failed to decode audio");
-
-    // Lower the background music volume
-    {
-        let bg_music_sink = bg_music_sink.lock().unwrap();
-        bg_music_sink.set_volume(0.2);
+    let (window_width, window_height) = window.get_size();
+    // Minimizada la ventana puede medir 0x0: solo se atienden sus eventos
+    if window_width == 0 || window_height == 0 {
+        window.update();
+        return;
+    }
+    if (framebuffer.width, framebuffer.height) == (window_width, window_height) {
+        window
+            .update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height)
+            .unwrap();
+        return;
     }
 
-    let sink = Sink::try_new(&stream_handle).expect("Failed to create sink");
-    sink.append(source);
-    sink.sleep_until_end(); // Esperar hasta que el sonido termine de reproducirse
+    if (screen.width, screen.height) != (window_width, window_height) {
+        *screen = Framebuffer::new(window_width, window_height);
+    }
+    framebuffer.scale_into(screen, filter);
+    window
+        .update_with_buffer(&screen.buffer, screen.width, screen.height)
+        .unwrap();
+}
 
-    // Restore the background music volume
-    let bg_music_sink = bg_music_sink.lock().unwrap();
-    bg_music_sink.set_volume(0.5);
+fn apply_settings(
+    settings: &Settings,
+    player: &mut Player,
+    minimap: &mut Minimap,
+    audio: &mut AudioManager,
+) {
+    player.fov = settings.fov();
+    minimap.rotate = settings.minimap_rotation;
+    minimap.show_enemy = settings.minimap_enemy;
+    audio.set_volumes(
+        settings.master_volume,
+        settings.music_volume,
+        settings.sfx_volume,
+    );
 }
 
-fn check_collision(player: &Player, sprite: &Sprite) -> bool {
-    let distance_x = player.pos.x - sprite.x;
-    let distance_y = player.pos.y - sprite.y;
-    let distance = (distance_x * distance_x + distance_y * distance_y).sqrt();
-    distance < 0.5 // Puedes ajustar este valor según el tamaño del sprite y el jugador
+fn save_settings(settings: &Settings) {
+    if let Err(e) = settings.save(SETTINGS_PATH) {
+        eprintln!("Failed to save settings to {}: {}", SETTINGS_PATH, e);
+    }
 }
 
-fn reset_game(player: &mut Player, sprite: &mut Sprite, maze: &mut Vec<Vec<char>>) {
-    *player = Player::new(1.5, 1.5, std::f32::consts::PI / 3.0, 0.02, 0.1);
-    *sprite = Sprite::new("./assets/sprite.png", 1.5, 3.5, 1.0, 0.007);
-    *maze = load_maze("./maze.txt");
+fn save_recording(recording: &Option<Replay>, path: &Option<String>) {
+    if let (Some(replay), Some(path)) = (recording, path) {
+        if let Err(e) = replay.save(path) {
+            eprintln!("Failed to save replay to {}: {}", path, e);
+        }
+    }
 }
 
 fn main() {
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let bg_music_sink = Arc::new(Mutex::new(Sink::try_new(&stream_handle).unwrap()));
-
-    // Start playing background music in a separate thread.
-    let bg_music_sink_clone = Arc::clone(&bg_music_sink);
-    let stream_handle_clone = stream_handle.clone();
-    thread::spawn(move || {
-        play_background_music(bg_music_sink_clone, stream_handle_clone);
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => exit_with_usage(&e),
+    };
+    ASSET_DIR.set(options.asset_dir.clone()).unwrap();
+    Lazy::force(&START_SCREEN);
+    Lazy::force(&LOST_SCREEN);
+    render::load_textures();
+
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("Failed to start the render thread pool");
+    }
+
+    let replay = options.replay.as_ref().map(|path| {
+        Replay::load(path)
+            .unwrap_or_else(|e| exit_with_usage(&format!("Failed to load replay {}: {}", path, e)))
     });
 
-    let width = 1300; // Framebuffer width
-    let height = 900; // Framebuffer height
-    let mut framebuffer = Framebuffer::new(width, height);
+    if let Some(frames) = options.benchmark {
+        bench::run_benchmark(&options, frames);
+        return;
+    }
 
-    let mut window = Window::new(
-        "The B∀CKROOMS",
-        (width as f32 / 1.3) as usize,
-        (height as f32 / 1.3) as usize,
-        WindowOptions::default(),
-    )
-    .unwrap_or_else(|e| {
-        panic!("{}", e);
-    });
+    // `parse_args` ya exige `--replay` con `--headless`
+    let replay = match replay {
+        Some(replay) if options.headless => return run_headless_replay(replay),
+        replay => replay,
+    };
 
-    let block_size = 100; // Block size in pixels
+    let mut playback = replay.map(Playback::new);
 
-    // Set up player
-    let mut player = Player::new(1.5, 1.5, std::f32::consts::PI / 3.0, 0.02, 0.1);
+    let mut settings = Settings::load(SETTINGS_PATH);
+    if let Some(scale) = options.render_scale {
+        settings.resolution_scale = scale;
+    }
+    if let Some(filter) = options.upscale {
+        settings.upscale_filter = filter;
+    }
 
-    let mut sprite = Sprite::new("./assets/sprite.png", 1.5, 3.5, 1.0, 0.007);
+    // Set up player, sprite and maze (un replay trae su propio nivel y semilla).
+    // Se carga antes de abrir la ventana para que un nivel inválido no la abra.
+    let (mut player, mut sprite, mut maze, mut doors, mut level_items, mut lightmap) =
+        match &playback {
+            Some(playback) => {
+                let replay = playback.replay();
+                new_run(
+                    &replay.level,
+                    replay.seed,
+                    replay.difficulty,
+                    settings.fov(),
+                )
+            }
+            None => new_run(
+                &options.level,
+                options.seed.unwrap_or(0),
+                options.difficulty,
+                settings.fov(),
+            ),
+        }
+        .unwrap_or_else(|e| exit_with_usage(&e));
+
+    let mut audio = AudioManager::new(&options.asset_dir);
+    audio.set_volumes(
+        settings.master_volume,
+        settings.music_volume,
+        settings.sfx_volume,
+    );
+    audio.play_music();
+
+    let width = options.width; // Framebuffer width
+    let height = options.height; // Framebuffer height
+    let mut framebuffer = new_scaled_framebuffer(width, height, &settings);
+    // Imagen al tamaño de la ventana cuando hay que escalar el framebuffer
+    let mut screen = Framebuffer::new(0, 0);
+
+    // minifb no tiene pantalla completa real: se usa una ventana sin bordes
+    // del tamaño de la resolución, encima de las demás
+    let (window_width, window_height, window_options) = if options.fullscreen {
+        let window_options = WindowOptions {
+            borderless: true,
+            title: false,
+            topmost: true,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        (width, height, window_options)
+    } else {
+        (
+            (width as f32 / options.window_scale) as usize,
+            (height as f32 / options.window_scale) as usize,
+            WindowOptions::default(),
+        )
+    };
+
+    let mut window = Window::new("The B∀CKROOMS", window_width, window_height, window_options)
+        .unwrap_or_else(|e| {
+            panic!("{}", e);
+        });
+
+    let block_size = 100; // Block size in pixels
 
-    let mut maze = load_maze("./maze.txt");
+    // Celdas descubiertas del nivel actual
+    let mut minimap = Minimap::new(&maze);
+    minimap.rotate = settings.minimap_rotation;
+    minimap.show_enemy = settings.minimap_enemy;
 
     let mut state = GameState::StartScreen; // Initial state
 
+    // Un replay arranca directamente en la partida
+    if playback.is_some() {
+        state = GameState::Playing;
+    }
+
     let mut last_mouse_pos = window
         .get_mouse_pos(MouseMode::Pass)
         .unwrap_or((0.0, 0.0))
         .0;
 
-    let sensitivity = 0.003; // Mouse sensitivity
+    // Opción seleccionada en el menú de pausa y en la pantalla de configuración
+    let mut pause_selected = 0;
+    let mut settings_selected = 0;
 
-    let footstep_sink = Arc::new(Mutex::new(None));
+    // Grabación de la partida en curso (solo con --record)
+    let mut recording: Option<Replay> = None;
 
     let mut timer = Timer::new(); // Timer instance
     let mut gilrs = Gilrs::new().unwrap(); // Gilrs instance
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
+    let mut quit = false;
+
+    // Vista cenital a pantalla completa, alternada con Tab
+    let mut top_down = false;
+
+    // Último mensaje del HUD y los ticks que le quedan en pantalla
+    let mut message: Option<(String, usize)> = None;
+
+    // Ticks de simulación, para las animaciones del render
+    let mut tick: u64 = 0;
+
+    // Tiempos por etapa del render; solo el modo benchmark los informa
+    let mut render_times = StageTimes::default();
+
+    while window.is_open() && !quit {
         framebuffer.clear();
 
         match state {
             GameState::StartScreen => {
                 render_start_screen(&mut framebuffer);
+                if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
+                    quit = true;
+                }
                 // Start the game on any key press
                 if window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No)
                     || window.is_key_pressed(Key::Space, minifb::KeyRepeat::No)
@@ -315,70 +610,254 @@ fn main() {
                     || window.is_key_pressed(Key::Up, minifb::KeyRepeat::No)
                     || window.is_key_pressed(Key::Down, minifb::KeyRepeat::No)
                 {
-                    reset_game(&mut player, &mut sprite, &mut maze);
+                    let seed = options.seed.unwrap_or_else(new_seed);
+                    (player, sprite, maze, doors, level_items, lightmap) =
+                        new_run(&options.level, seed, options.difficulty, settings.fov())
+                            .unwrap_or_else(|e| exit_with_usage(&e));
+                    minimap.reset(&maze);
+                    message = None;
+                    tick = 0;
                     state = GameState::Playing;
+
+                    if options.record.is_some() {
+                        recording = Some(Replay::new(seed, &options.level, options.difficulty));
+                    }
                 }
             }
             GameState::Playing => {
-                // In your main loop where you call process_events:
-                process_events(
+                if window.is_key_pressed(Key::Tab, minifb::KeyRepeat::No) {
+                    top_down = !top_down;
+                }
+
+                // La entrada viene del replay si se está reproduciendo uno
+                let live_input = read_input(
                     &window,
-                    &mut player,
-                    &maze,
-                    block_size,
                     &mut gilrs,
-                    &stream_handle,
-                    &footstep_sink,
+                    &mut last_mouse_pos,
+                    settings.mouse_sensitivity,
                 );
+                let input = if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
+                    audio.set_footsteps(false);
+                    pause_selected = 0;
+                    state = GameState::Paused;
+                    None
+                } else {
+                    match playback.as_mut() {
+                        Some(playback) => {
+                            let frame = playback.next_frame();
+                            if frame.is_none() {
+                                // Fin del replay: volver a la pantalla de inicio
+                                audio.set_footsteps(false);
+                                state = GameState::StartScreen;
+                            }
+                            frame
+                        }
+                        None => Some(live_input),
+                    }
+                };
 
-                // Mueve el sprite hacia el jugador
-                sprite.move_towards_player(&player, &maze, block_size);
-
-                // Manejar la rotación del ratón
-                if let Some((mouse_x, _)) = window.get_mouse_pos(MouseMode::Pass) {
-                    let delta = mouse_x - last_mouse_pos;
-                    player.angle += delta * sensitivity;
-                    if player.angle < 0.0 {
-                        player.angle += 2.0 * std::f32::consts::PI;
+                if let Some(input) = input {
+                    if let Some(recording) = recording.as_mut() {
+                        recording.record(input);
                     }
-                    if player.angle >= 2.0 * std::f32::consts::PI {
-                        player.angle -= 2.0 * std::f32::consts::PI;
+
+                    let (player_moved, outcome, new_message) = simulate_tick(
+                        &input,
+                        &mut player,
+                        &mut sprite,
+                        &mut maze,
+                        &mut doors,
+                        &mut level_items,
+                        &lightmap,
+                        tick,
+                        block_size,
+                    );
+                    tick += 1;
+                    minimap.reveal(&player, &maze, &doors, block_size);
+
+                    message = match new_message {
+                        Some(text) => {
+                            let ticks = MESSAGE_TICKS.max(text.len() * MESSAGE_TICKS_PER_CHAR);
+                            Some((text, ticks))
+                        }
+                        None => message
+                            .and_then(|(text, ticks)| (ticks > 1).then_some((text, ticks - 1))),
+                    };
+
+                    // Pasos solo mientras el jugador se mueve
+                    audio.set_footsteps(player_moved);
+                    audio.set_enemy(Some(
+                        sprite.audio_position(&player, &maze, &doors, block_size),
+                    ));
+                    audio.set_music_intensity(sprite.threat(&player, &maze, &doors, block_size));
+
+                    if let Some(outcome) = outcome {
+                        state = outcome;
+                        audio.set_footsteps(false);
+                        save_recording(&recording, &options.record);
+                        playback = None;
+
+                        audio.play_stinger(match outcome {
+                            GameState::Won => Sound::Won,
+                            _ => Sound::GameOver,
+                        });
                     }
-                    update_direction(&mut player);
-                    last_mouse_pos = mouse_x;
                 }
 
                 // Renderizar la vista 3D o 2D
-                render3d(&mut framebuffer, &mut player, &maze, &sprite);
-
-                // Verificar si el jugador ha ganado
-                if has_won(player.pos.x, player.pos.y, &maze, block_size) {
-                    state = GameState::Won;
-
-                    let bg_music_sink_clone = Arc::clone(&bg_music_sink);
-                    let stream_handle_clone = stream_handle.clone();
-                    thread::spawn(move || {
-                        play_sound_effect(
-                            "./assets/change.mp3",
-                            bg_music_sink_clone,
-                            stream_handle_clone,
-                        );
-                    });
+                let scene = Scene {
+                    player: &player,
+                    maze: &maze,
+                    doors: &doors,
+                    items: &level_items,
+                    lightmap: &lightmap,
+                    sprite: &sprite,
+                    minimap: &minimap,
+                    view: player
+                        .camera
+                        .view(settings.head_bob, settings.screen_shake, tick),
+                    tick,
+                };
+                render_scene(
+                    &mut framebuffer,
+                    top_down,
+                    &scene,
+                    &settings.effects(),
+                    &mut render_times,
+                );
+                render_hud(
+                    &mut framebuffer,
+                    &player,
+                    message.as_ref().map(|(text, _)| text.as_str()),
+                );
+            }
+            GameState::Paused => {
+                let scene = Scene {
+                    player: &player,
+                    maze: &maze,
+                    doors: &doors,
+                    items: &level_items,
+                    lightmap: &lightmap,
+                    sprite: &sprite,
+                    minimap: &minimap,
+                    view: player
+                        .camera
+                        .view(settings.head_bob, settings.screen_shake, tick),
+                    tick,
+                };
+                render_scene(
+                    &mut framebuffer,
+                    top_down,
+                    &scene,
+                    &settings.effects(),
+                    &mut render_times,
+                );
+                let items: Vec<String> = PAUSE_ITEMS.iter().map(|s| s.to_string()).collect();
+                render_menu(&mut framebuffer, "Paused", &items, pause_selected);
+
+                match read_menu_action(&window) {
+                    Some(MenuAction::Back) => state = GameState::Playing,
+                    Some(MenuAction::Select) => match pause_selected {
+                        0 => state = GameState::Playing,
+                        1 => {
+                            settings_selected = 0;
+                            state = GameState::Settings;
+                        }
+                        2 => {
+                            // La partida abandonada se guarda como al salir o morir
+                            save_recording(&recording, &options.record);
+                            let seed = options.seed.unwrap_or_else(new_seed);
+                            (player, sprite, maze, doors, level_items, lightmap) =
+                                new_run(&options.level, seed, options.difficulty, settings.fov())
+                                    .unwrap_or_else(|e| exit_with_usage(&e));
+                            minimap.reset(&maze);
+                            message = None;
+                            tick = 0;
+                            if options.record.is_some() {
+                                recording =
+                                    Some(Replay::new(seed, &options.level, options.difficulty));
+                            }
+                            playback = None;
+                            state = GameState::Playing;
+                        }
+                        _ => {
+                            save_recording(&recording, &options.record);
+                            recording = None;
+                            playback = None;
+                            state = GameState::StartScreen;
+                        }
+                    },
+                    Some(action) => {
+                        pause_selected = move_selection(pause_selected, PAUSE_ITEMS.len(), action)
+                    }
+                    None => {}
                 }
 
-                // Verificar si el sprite colisiona con el jugador (lógica de pérdida)
-                if check_collision(&player, &sprite) {
-                    state = GameState::Lost;
-
-                    let bg_music_sink_clone = Arc::clone(&bg_music_sink);
-                    let stream_handle_clone = stream_handle.clone();
-                    thread::spawn(move || {
-                        play_sound_effect(
-                            "./assets/game-over.mp3",
-                            bg_music_sink_clone,
-                            stream_handle_clone,
-                        );
-                    });
+                // Evitar que el ratón gire la cámara de golpe al reanudar
+                if state == GameState::Playing {
+                    if let Some((mouse_x, _)) = window.get_mouse_pos(MouseMode::Pass) {
+                        last_mouse_pos = mouse_x;
+                    }
+                }
+            }
+            GameState::Settings => {
+                let scene = Scene {
+                    player: &player,
+                    maze: &maze,
+                    doors: &doors,
+                    items: &level_items,
+                    lightmap: &lightmap,
+                    sprite: &sprite,
+                    minimap: &minimap,
+                    view: player
+                        .camera
+                        .view(settings.head_bob, settings.screen_shake, tick),
+                    tick,
+                };
+                render_scene(
+                    &mut framebuffer,
+                    top_down,
+                    &scene,
+                    &settings.effects(),
+                    &mut render_times,
+                );
+                let mut items: Vec<String> = (0..Settings::option_count())
+                    .map(|i| settings.label(i))
+                    .collect();
+                items.push("Back".to_string());
+                render_menu(&mut framebuffer, "Settings", &items, settings_selected);
+
+                let back = items.len() - 1;
+                let action = read_menu_action(&window);
+                match action {
+                    Some(MenuAction::Left) | Some(MenuAction::Right)
+                        if settings_selected < back =>
+                    {
+                        let direction = if action == Some(MenuAction::Left) {
+                            -1
+                        } else {
+                            1
+                        };
+                        settings.adjust(settings_selected, direction);
+                        apply_settings(&settings, &mut player, &mut minimap, &mut audio);
+                        if (framebuffer.width, framebuffer.height)
+                            != scaled_size(width, height, &settings)
+                        {
+                            framebuffer = new_scaled_framebuffer(width, height, &settings);
+                        }
+                    }
+                    Some(MenuAction::Back) => {
+                        save_settings(&settings);
+                        state = GameState::Paused;
+                    }
+                    Some(MenuAction::Select) if settings_selected == back => {
+                        save_settings(&settings);
+                        state = GameState::Paused;
+                    }
+                    Some(action) => {
+                        settings_selected = move_selection(settings_selected, items.len(), action)
+                    }
+                    None => {}
                 }
             }
             GameState::Lost => {
@@ -392,16 +871,19 @@ fn main() {
                 {
                     // Aquí puedes bloquear la opción de reiniciar el juego hasta que termine la canción
                     // o simplemente no permitir reiniciar mientras `sink` sigue reproduciendo sonido.
-                    reset_game(&mut player, &mut sprite, &mut maze);
+                    // La partida nueva se prepara al salir de la pantalla de inicio.
                     state = GameState::StartScreen;
                 }
             }
             GameState::Won => {
-                framebuffer.draw_text(
+                let style = TextStyle::new(framebuffer.text_size(40.0), Color::from_hex(0x7F5A1B))
+                    .align(Align::Center)
+                    .wrap(framebuffer.width * 3 / 4);
+                framebuffer.draw_text_styled(
                     "You won! Press any key to return to start.",
-                    100,
-                    100,
-                    Color::from_hex(0x7F5A1B),
+                    framebuffer.width / 2,
+                    framebuffer.height / 3,
+                    &style,
                 );
                 if window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No)
                     || window.is_key_pressed(Key::Space, minifb::KeyRepeat::No)
@@ -412,20 +894,40 @@ fn main() {
                 {
                     // Aquí puedes bloquear la opción de reiniciar el juego hasta que termine la canción
                     // o simplemente no permitir reiniciar mientras `sink` sigue reproduciendo sonido.
-                    reset_game(&mut player, &mut sprite, &mut maze);
+                    // La partida nueva se prepara al salir de la pantalla de inicio.
                     state = GameState::StartScreen;
                 }
             }
         }
 
+        // El monstruo solo se oye durante la partida
+        if state != GameState::Playing {
+            audio.set_enemy(None);
+            audio.set_music_intensity(0.0);
+        }
+        audio.update();
+
         // Update timer and display FPS
         timer.update();
         let fps_text = format!("FPS: {:.2}", timer.get_fps());
-        framebuffer.draw_text(&fps_text, 10, 10, Color::from_hex(0xFFFFFF));
+        let fps_style = TextStyle::new(framebuffer.text_size(32.0), Color::from_hex(0xFFFFFF))
+            .shadow(Color::from_hex(0x000000));
+        framebuffer.draw_text_styled(&fps_text, 10, 10, &fps_style);
 
         // Render framebuffer to window
-        window
-            .update_with_buffer(&framebuffer.to_u32_buffer(), width, height)
-            .unwrap();
+        present(
+            &mut window,
+            &framebuffer,
+            &mut screen,
+            settings.upscale_filter,
+        );
+    }
+
+    // Guardar la partida si se cerró la ventana en medio de una grabación
+    if matches!(
+        state,
+        GameState::Playing | GameState::Paused | GameState::Settings
+    ) {
+        save_recording(&recording, &options.record);
     }
 }
@@ -1,12 +1,373 @@
+use crate::doors::{DOOR, LOCKED_DOOR};
+use crate::items::ItemKind;
+use crate::lightmap::{LightSource, EXIT_SIGN, FLUORESCENT};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-pub fn load_maze(filename: &str) -> Vec<Vec<char>> {
-    let file = File::open(filename).unwrap();
+// Fracción de los pasos entre celdas del laberinto generado que llevan puerta
+const DOOR_CHANCE: f64 = 0.125;
+
+// Fracción de las celdas del laberinto generado con agua, una batería o una nota
+const ITEM_CHANCE: f64 = 0.1;
+
+// Fracción de las celdas del laberinto generado con un panel fluorescente
+const LIGHT_CHANCE: f64 = 0.2;
+
+/// Luces definidas por un nivel: cada carácter con su fuente de luz.
+pub type Palette = Vec<(char, LightSource)>;
+
+/// Columna más alta que una pared normal.
+pub const PILLAR: char = '#';
+/// Pared baja (una baranda o un mostrador) por encima de la que se ve.
+pub const HALF_WALL: char = '=';
+/// Celda de techo bajo: se camina por debajo de una viga que cuelga del techo.
+pub const LOW_CEILING: char = '^';
+
+/// Suelo a cielo abierto: en lugar del techo se ve el cielo.
+pub const OPEN_SKY: char = '*';
+
+/// Ventana de vidrio: se ve y pasa la luz a través de ella, pero no se cruza.
+pub const WINDOW: char = 'W';
+/// Reja de alambre: como una ventana, pero con huecos entre los alambres.
+pub const FENCE: char = '%';
+
+/// Paredes delgadas en un borde de la celda: oeste, este, norte y sur.
+pub const THIN_WEST: char = '[';
+pub const THIN_EAST: char = ']';
+pub const THIN_NORTH: char = '~';
+pub const THIN_SOUTH: char = '_';
+/// Pared diagonal de la esquina inferior izquierda a la superior derecha.
+pub const DIAGONAL_RISING: char = '/';
+/// Pared diagonal de la esquina superior izquierda a la inferior derecha.
+pub const DIAGONAL_FALLING: char = '\\';
+
+/// Tramo vertical que ocupa una pared, en alturas de pared: el suelo está en
+/// 0.0 y el techo en 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl Span {
+    /// Una pared normal, del suelo al techo.
+    pub const FULL: Span = Span {
+        bottom: 0.0,
+        top: 1.0,
+    };
+
+    /// Si tapa todo lo que hay detrás hasta la altura `height`.
+    pub fn covers(&self, height: f32) -> bool {
+        self.bottom <= 0.0 && self.top >= height
+    }
+}
+
+/// Pared sin grosor entre dos puntos del nivel (en celdas).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+}
+
+impl Segment {
+    /// Dónde corta el rayo que sale de `origin` con dirección unitaria `dir`:
+    /// la distancia recorrida y la posición en el segmento (de 0.0 en `from`
+    /// a 1.0 en `to`).
+    pub fn intersect(&self, origin: (f32, f32), dir: (f32, f32)) -> Option<(f32, f32)> {
+        let edge = (self.to.0 - self.from.0, self.to.1 - self.from.1);
+        let denom = cross(dir, edge);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+        let offset = (self.from.0 - origin.0, self.from.1 - origin.1);
+        let distance = cross(offset, edge) / denom;
+        let along = cross(offset, dir) / denom;
+        (distance >= 0.0 && (0.0..=1.0).contains(&along)).then_some((distance, along))
+    }
+
+    /// Si la línea de `a` a `b` pasa a través del segmento.
+    pub fn crosses(&self, a: (f32, f32), b: (f32, f32)) -> bool {
+        let length = (b.0 - a.0).hypot(b.1 - a.1);
+        let dir = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        self.intersect(a, dir)
+            .is_some_and(|(distance, _)| distance <= length)
+    }
+
+    /// Distancia del punto (x, y) al punto más cercano del segmento.
+    pub fn distance_to(&self, (x, y): (f32, f32)) -> f32 {
+        let edge = (self.to.0 - self.from.0, self.to.1 - self.from.1);
+        let t = ((x - self.from.0) * edge.0 + (y - self.from.1) * edge.1)
+            / (edge.0 * edge.0 + edge.1 * edge.1);
+        let t = t.clamp(0.0, 1.0);
+        (x - self.from.0 - edge.0 * t).hypot(y - self.from.1 - edge.1 * t)
+    }
+}
+
+fn cross(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/// La pared delgada de la celda (x, y), si la tiene.
+pub fn thin_wall(cell: char, x: usize, y: usize) -> Option<Segment> {
+    let (from, to) = match cell {
+        THIN_WEST => ((0.0, 0.0), (0.0, 1.0)),
+        THIN_EAST => ((1.0, 0.0), (1.0, 1.0)),
+        THIN_NORTH => ((0.0, 0.0), (1.0, 0.0)),
+        THIN_SOUTH => ((0.0, 1.0), (1.0, 1.0)),
+        DIAGONAL_RISING => ((0.0, 1.0), (1.0, 0.0)),
+        DIAGONAL_FALLING => ((0.0, 0.0), (1.0, 1.0)),
+        _ => return None,
+    };
+    let (x, y) = (x as f32, y as f32);
+    Some(Segment {
+        from: (x + from.0, y + from.1),
+        to: (x + to.0, y + to.1),
+    })
+}
+
+/// Celdas por las que se camina y que los rayos atraviesan (los objetos están
+/// sobre el suelo y las vigas de los techos bajos, por encima de la cabeza).
+/// Las celdas con una pared delgada también, salvo por su pared: ver
+/// `thin_wall`.
+pub fn is_floor(cell: char) -> bool {
+    cell == ' '
+        || cell == LOW_CEILING
+        || cell == OPEN_SKY
+        || ItemKind::from_cell(cell).is_some()
+        || thin_wall(cell, 0, 0).is_some()
+}
+
+/// Paredes que dejan ver lo que hay detrás, según la opacidad de su textura.
+pub fn is_transparent(cell: char) -> bool {
+    cell == WINDOW || cell == FENCE
+}
+
+/// Tramo que ocupa la pared de una celda, o `None` si no tiene nada.
+pub fn wall_span(cell: char) -> Option<Span> {
+    match cell {
+        PILLAR => Some(Span {
+            bottom: 0.0,
+            top: 2.0,
+        }),
+        HALF_WALL => Some(Span {
+            bottom: 0.0,
+            top: 0.4,
+        }),
+        LOW_CEILING => Some(Span {
+            bottom: 0.75,
+            top: 1.0,
+        }),
+        c if is_floor(c) => None,
+        _ => Some(Span::FULL),
+    }
+}
+
+/// Lee un archivo de nivel: las filas del laberinto y, en líneas que empiezan
+/// por `light`, las luces que define.
+pub fn load_maze(filename: &str) -> Result<(Vec<Vec<char>>, Palette), String> {
+    let file = File::open(filename).map_err(|e| format!("Can't open level {}: {}", filename, e))?;
     let reader = BufReader::new(file);
 
-    reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect()
+    let mut maze = Vec::new();
+    let mut palette = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("Can't read level {}: {}", filename, e))?;
+        if line.starts_with("light ") {
+            let light = LightSource::parse(&line)
+                .map_err(|e| format!("Invalid light in {}: `{}`: {}", filename, line, e))?;
+            palette.push(light);
+        } else {
+            maze.push(line.chars().collect());
+        }
+    }
+    if maze.iter().all(Vec::is_empty) {
+        return Err(format!("Level {} is empty", filename));
+    }
+    Ok((maze, palette))
+}
+
+/// Carga un nivel a partir de su descripción: la ruta de un archivo o
+/// `generated <columnas>x<filas>`, que genera un laberinto con `seed`.
+pub fn load_level(level: &str, seed: u64) -> Result<(Vec<Vec<char>>, Palette), String> {
+    match level.strip_prefix("generated ") {
+        Some(size) => {
+            let (cols, rows) = crate::cli::parse_size(size)
+                .map_err(|e| format!("Invalid generated level `{}`: {}", level, e))?;
+            Ok((generate_maze(cols, rows, seed), Vec::new()))
+        }
+        None => load_maze(level),
+    }
+}
+
+/// Genera un laberinto perfecto de `cols` x `rows` celdas con el mismo formato
+/// que `maze.txt` (`+--+` y `|  |`), usando un recorrido en profundidad.
+/// El jugador empieza en la celda superior izquierda y la meta `g` queda en la
+/// esquina opuesta, tras una puerta con llave (`L`); la llave (`k`) está en
+/// alguna celda alcanzable sin pasar por esa puerta. Algunos pasos más llevan
+/// puertas normales (`D`), algunas celdas otros objetos o un panel
+/// fluorescente (`F`), y frente a la meta cuelga un cartel de salida (`E`).
+pub fn generate_maze(cols: usize, rows: usize, seed: u64) -> Vec<Vec<char>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Cada celda ocupa 3 columnas y 2 filas de caracteres, más el borde final
+    let mut maze = vec![vec![' '; cols * 3 + 1]; rows * 2 + 1];
+    for (y, line) in maze.iter_mut().enumerate() {
+        for (x, c) in line.iter_mut().enumerate() {
+            *c = match (y % 2 == 0, x % 3 == 0) {
+                (true, true) => '+',
+                (true, false) => '-',
+                (false, true) => '|',
+                (false, false) => ' ',
+            };
+        }
+    }
+
+    let mut visited = vec![vec![false; cols]; rows];
+    // Celda desde la que se llegó a cada una: el árbol del recorrido
+    let mut parent = vec![vec![None; cols]; rows];
+    let mut stack = vec![(0, 0)];
+    visited[0][0] = true;
+
+    while let Some(&(cx, cy)) = stack.last() {
+        let mut neighbours = Vec::with_capacity(4);
+        if cx > 0 && !visited[cy][cx - 1] {
+            neighbours.push((cx - 1, cy));
+        }
+        if cx + 1 < cols && !visited[cy][cx + 1] {
+            neighbours.push((cx + 1, cy));
+        }
+        if cy > 0 && !visited[cy - 1][cx] {
+            neighbours.push((cx, cy - 1));
+        }
+        if cy + 1 < rows && !visited[cy + 1][cx] {
+            neighbours.push((cx, cy + 1));
+        }
+
+        match neighbours.choose(&mut rng) {
+            Some(&(nx, ny)) => {
+                // Abrir la pared entre la celda actual y la vecina
+                if nx != cx {
+                    let wall_x = cx.max(nx) * 3;
+                    maze[cy * 2 + 1][wall_x] = ' ';
+                } else {
+                    let wall_y = cy.max(ny) * 2;
+                    maze[wall_y][cx * 3 + 1] = ' ';
+                    maze[wall_y][cx * 3 + 2] = ' ';
+                }
+                visited[ny][nx] = true;
+                parent[ny][nx] = Some((cx, cy));
+                stack.push((nx, ny));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+
+    let goal = (cols - 1, rows - 1);
+    for cy in 0..rows {
+        for cx in 0..cols {
+            let Some(from) = parent[cy][cx] else {
+                continue;
+            };
+            if (cx, cy) == goal {
+                lock_goal(&mut maze, &parent, goal, &mut rng);
+            } else if rng.gen_bool(DOOR_CHANCE) {
+                set_passage(&mut maze, from, (cx, cy), DOOR);
+            }
+        }
+    }
+
+    let extras = [ItemKind::AlmondWater, ItemKind::Battery, ItemKind::Note];
+    for cy in 0..rows {
+        for cx in 0..cols {
+            let cell = &mut maze[cy * 2 + 1][cx * 3 + 1];
+            if (cx, cy) != (0, 0) && *cell == ' ' && rng.gen_bool(ITEM_CHANCE) {
+                *cell = extras[rng.gen_range(0..extras.len())].cell();
+            }
+        }
+    }
+
+    // Los paneles van en la segunda columna de la celda, así no pisan objetos
+    for cy in 0..rows {
+        for cx in 0..cols {
+            if rng.gen_bool(LIGHT_CHANCE) {
+                maze[cy * 2 + 1][cx * 3 + 2] = FLUORESCENT;
+            }
+        }
+    }
+
+    maze[rows * 2 - 1][cols * 3 - 2] = EXIT_SIGN;
+    maze[rows * 2 - 1][cols * 3 - 1] = 'g';
+    maze
+}
+
+// Pone la puerta con llave en el paso por el que se llega a la meta y la llave
+// en una celda al azar fuera de lo que queda detrás de esa puerta. En un
+// laberinto perfecto eso es todo salvo las celdas que cuelgan de la meta.
+fn lock_goal(
+    maze: &mut [Vec<char>],
+    parent: &[Vec<Option<(usize, usize)>>],
+    goal: (usize, usize),
+    rng: &mut StdRng,
+) {
+    let Some(from) = parent[goal.1][goal.0] else {
+        return;
+    };
+
+    let behind_goal = |mut cell: (usize, usize)| loop {
+        if cell == goal {
+            return true;
+        }
+        match parent[cell.1][cell.0] {
+            Some(up) => cell = up,
+            None => return false,
+        }
+    };
+    let mut candidates = Vec::new();
+    for (cy, row) in parent.iter().enumerate() {
+        for cx in 0..row.len() {
+            if (cx, cy) != (0, 0) && !behind_goal((cx, cy)) {
+                candidates.push((cx, cy));
+            }
+        }
+    }
+
+    if let Some(&(kx, ky)) = candidates.choose(rng) {
+        set_passage(maze, from, goal, LOCKED_DOOR);
+        maze[ky * 2 + 1][kx * 3 + 1] = ItemKind::Key.cell();
+    }
+}
+
+// Escribe `cell` en el hueco abierto entre dos celdas vecinas
+fn set_passage(maze: &mut [Vec<char>], a: (usize, usize), b: (usize, usize), cell: char) {
+    if a.0 != b.0 {
+        maze[a.1 * 2 + 1][a.0.max(b.0) * 3] = cell;
+    } else {
+        let wall_y = a.1.max(b.1) * 2;
+        maze[wall_y][a.0 * 3 + 1] = cell;
+        maze[wall_y][a.0 * 3 + 2] = cell;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_or_invalid_levels_are_errors() {
+        let missing = load_level("no/such/level.txt", 0).unwrap_err();
+        assert!(missing.starts_with("Can't open level no/such/level.txt"));
+        assert!(load_level("generated 4", 0).is_err());
+        assert!(load_level("generated 4x3", 0).is_ok());
+    }
+
+    #[test]
+    fn generated_mazes_depend_only_on_the_seed() {
+        assert_eq!(generate_maze(6, 4, 7), generate_maze(6, 4, 7));
+        assert_ne!(generate_maze(6, 4, 7), generate_maze(6, 4, 8));
+    }
 }
@@ -0,0 +1,136 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::text::{measure_text, Align, TextStyle};
+use minifb::{Key, KeyRepeat, Window};
+
+pub const PAUSE_ITEMS: [&str; 4] = ["Resume", "Settings", "Restart", "Quit to title"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+/// Lee la tecla de navegación pulsada en este cuadro, si hay alguna.
+pub fn read_menu_action(window: &Window) -> Option<MenuAction> {
+    let pressed = |keys: &[Key]| {
+        keys.iter()
+            .any(|&k| window.is_key_pressed(k, KeyRepeat::Yes))
+    };
+
+    if pressed(&[Key::Up, Key::W]) {
+        Some(MenuAction::Up)
+    } else if pressed(&[Key::Down, Key::S]) {
+        Some(MenuAction::Down)
+    } else if pressed(&[Key::Left, Key::A]) {
+        Some(MenuAction::Left)
+    } else if pressed(&[Key::Right, Key::D]) {
+        Some(MenuAction::Right)
+    } else if window.is_key_pressed(Key::Enter, KeyRepeat::No)
+        || window.is_key_pressed(Key::Space, KeyRepeat::No)
+    {
+        Some(MenuAction::Select)
+    } else if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+        Some(MenuAction::Back)
+    } else {
+        None
+    }
+}
+
+/// Mueve la selección hacia arriba o abajo dando la vuelta en los extremos.
+pub fn move_selection(selected: usize, len: usize, action: MenuAction) -> usize {
+    match action {
+        MenuAction::Up => (selected + len - 1) % len,
+        MenuAction::Down => (selected + 1) % len,
+        _ => selected,
+    }
+}
+
+/// Oscurece la escena y dibuja un título con la lista de opciones encima,
+/// centrados en la pantalla.
+pub fn render_menu(framebuffer: &mut Framebuffer, title: &str, items: &[String], selected: usize) {
+    framebuffer.dim(0.35);
+
+    // Las listas largas (la configuración) empiezan más arriba y, si aun así
+    // no caben, usan letra más chica
+    let mut size = framebuffer.text_size(40.0);
+    let mut top = framebuffer.height / 4;
+    let rows_height = items.len() * TextStyle::new(size, Color::from_hex(0)).line_height();
+    if rows_height > framebuffer.height / 2 {
+        top = framebuffer.height / 8;
+        let room = framebuffer.height * 2 / 3;
+        size *= (room as f32 / rows_height as f32).min(1.0);
+    }
+    let shadow = Color::from_hex(0x000000);
+    let title_style = TextStyle::new(framebuffer.text_size(56.0), Color::from_hex(0xD6C34E))
+        .align(Align::Center)
+        .shadow(shadow);
+    let selected_style = TextStyle::new(size, Color::from_hex(0xFFFFFF)).shadow(shadow);
+    let item_style = TextStyle::new(size, Color::from_hex(0x88814a)).shadow(shadow);
+
+    // Las opciones van en una columna centrada; las que tienen valor
+    // (`Nombre: valor`) lo muestran alineado al borde derecho de la columna.
+    let rows: Vec<(String, Option<&str>)> = items
+        .iter()
+        .map(|item| match item.split_once(": ") {
+            Some((label, value)) => (label.to_string(), Some(value)),
+            None => (item.clone(), None),
+        })
+        .collect();
+    let gap = measure_text("    ", &item_style).0;
+    let column_width = rows
+        .iter()
+        .map(|(label, value)| {
+            let value_width = value.map_or(0, |value| gap + measure_text(value, &item_style).0);
+            measure_text(label, &item_style).0 + value_width
+        })
+        .max()
+        .unwrap_or(0);
+    let x = framebuffer.width.saturating_sub(column_width) / 2;
+    let line_height = item_style.line_height();
+    let mut y = top;
+
+    y += framebuffer.draw_text_styled(title, framebuffer.width / 2, y, &title_style);
+    y += line_height / 2;
+
+    // Marco del panel alrededor de la columna de opciones
+    let padding = line_height;
+    let title_width = measure_text(title, &title_style).0;
+    let panel_width = column_width.max(title_width) + padding * 2;
+    let panel_height = y - top + rows.len() * line_height + padding;
+    framebuffer.draw_rect_outline(
+        framebuffer.width.saturating_sub(panel_width) / 2,
+        top.saturating_sub(padding / 2),
+        panel_width,
+        panel_height,
+        (line_height / 16).max(1),
+        Color::from_hex(0xD6C34E),
+    );
+
+    for (i, (label, value)) in rows.iter().enumerate() {
+        let style = if i == selected {
+            // La fuente no tiene `>`: el marcador es un triángulo
+            let marker_x = x as i32 - line_height as i32 / 2;
+            let marker_y = (y + line_height / 2) as i32;
+            let half = (line_height / 5) as i32;
+            let marker = (
+                (marker_x - half, marker_y - half),
+                (marker_x - half, marker_y + half),
+                (marker_x + half / 2, marker_y),
+            );
+            framebuffer.fill_triangle(marker.0, marker.1, marker.2, Color::from_hex(0xFFFFFF));
+            framebuffer.draw_triangle(marker.0, marker.1, marker.2, Color::from_hex(0x000000));
+            selected_style
+        } else {
+            item_style
+        };
+        if let Some(value) = value {
+            framebuffer.draw_text_styled(value, x + column_width, y, &style.align(Align::Right));
+        }
+        y += framebuffer.draw_text_styled(label, x, y, &style);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cast_ray::cast_rays;
+use crate::color::Color;
+use crate::doors::Doors;
+use crate::framebuffer::Framebuffer;
+use crate::maze::thin_wall;
+use crate::player::Player;
+use crate::render::cell_color;
+use crate::sprite::Sprite;
+use std::f32::consts::FRAC_PI_2;
+
+// Rayos lanzados por tick para descubrir celdas dentro del campo de visión
+const REVEAL_RAYS: usize = 120;
+
+// Paso (en celdas) al recorrer cada rayo marcando celdas vistas
+const REVEAL_STEP: f32 = 0.2;
+
+// Tamaño máximo de una celda del mapa y fracción de la pantalla que puede ocupar
+const MAX_CELL_SIZE: f32 = 12.0;
+const MAX_SCREEN_FRACTION: f32 = 1.0 / 3.0;
+
+// Celdas visibles alrededor del jugador en el mapa giratorio
+const ROTATING_RADIUS: f32 = 8.0;
+
+// Largo del cono de visión, en celdas
+const VIEW_CONE_LENGTH: f32 = 3.0;
+
+// Medio grosor con que se dibujan las paredes delgadas, en celdas
+const THIN_WALL_WIDTH: f32 = 0.12;
+
+const FOG_COLOR: u32 = 0x1A1505;
+const MARGIN: usize = 10;
+
+/// Mini-mapa con niebla de guerra: solo muestra las celdas que el jugador
+/// ya vio. Puede girar con el jugador (arriba es hacia donde mira) y
+/// mostrar u ocultar al monstruo.
+pub struct Minimap {
+    seen: Vec<Vec<bool>>,
+    pub rotate: bool,
+    pub show_enemy: bool,
+}
+
+impl Minimap {
+    pub fn new(maze: &[Vec<char>]) -> Self {
+        Minimap {
+            seen: maze.iter().map(|row| vec![false; row.len()]).collect(),
+            rotate: false,
+            show_enemy: false,
+        }
+    }
+
+    /// Olvida lo descubierto (para un nivel nuevo), conservando las opciones.
+    pub fn reset(&mut self, maze: &[Vec<char>]) {
+        self.seen = maze.iter().map(|row| vec![false; row.len()]).collect();
+    }
+
+    /// Marca como vistas las celdas que recorren los rayos del campo de
+    /// visión, incluida la pared que detiene a cada uno.
+    pub fn reveal(
+        &mut self,
+        player: &Player,
+        maze: &[Vec<char>],
+        doors: &Doors,
+        block_size: usize,
+    ) {
+        self.mark(player.pos.x, player.pos.y);
+
+        for i in 0..=REVEAL_RAYS {
+            let a = player.angle - player.fov / 2.0 + player.fov * i as f32 / REVEAL_RAYS as f32;
+            let hit = cast_rays(None, maze, doors, player, a, block_size);
+            let distance = hit.distance / block_size as f32;
+
+            let mut t = 0.0;
+            while t <= distance + REVEAL_STEP {
+                self.mark(player.pos.x + a.cos() * t, player.pos.y + a.sin() * t);
+                t += REVEAL_STEP;
+            }
+        }
+    }
+
+    fn mark(&mut self, x: f32, y: f32) {
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+        if let Some(cell) = self
+            .seen
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *cell = true;
+        }
+    }
+
+    // Color de la posición (x, y) del laberinto, o niebla si no se ha visto
+    fn color_at(&self, maze: &[Vec<char>], x: f32, y: f32) -> u32 {
+        if x < 0.0 || y < 0.0 {
+            return FOG_COLOR;
+        }
+        let (col, row) = (x as usize, y as usize);
+        match self.seen.get(row).and_then(|r| r.get(col)) {
+            Some(true) => match thin_wall(maze[row][col], col, row) {
+                Some(segment) if segment.distance_to((x, y)) < THIN_WALL_WIDTH => cell_color('-'),
+                _ => cell_color(maze[row][col]),
+            },
+            _ => FOG_COLOR,
+        }
+    }
+
+    /// Dibuja el mapa en la esquina inferior derecha.
+    pub fn render(
+        &self,
+        framebuffer: &mut Framebuffer,
+        player: &Player,
+        maze: &[Vec<char>],
+        doors: &Doors,
+        sprite: &Sprite,
+        block_size: usize,
+    ) {
+        let rows = maze.len() as f32;
+        let cols = maze.iter().map(Vec::len).max().unwrap_or(0) as f32;
+        let scale = framebuffer.height as f32 / 900.0;
+
+        // Tamaño de celda y de la ventana del mapa en píxeles
+        let (cell, width, height) = if self.rotate {
+            let size = (240.0 * scale) as usize;
+            (size as f32 / (ROTATING_RADIUS * 2.0), size, size)
+        } else {
+            let cell = (MAX_CELL_SIZE * scale)
+                .min(framebuffer.width as f32 * MAX_SCREEN_FRACTION / cols)
+                .min(framebuffer.height as f32 * MAX_SCREEN_FRACTION / rows)
+                .max(1.0);
+            (cell, (cols * cell) as usize, (rows * cell) as usize)
+        };
+        let left = framebuffer.width.saturating_sub(width + MARGIN);
+        let top = framebuffer.height.saturating_sub(height + MARGIN);
+        let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+
+        // Giro para que la dirección del jugador quede hacia arriba
+        let theta = player.angle + FRAC_PI_2;
+        let (sin, cos) = theta.sin_cos();
+
+        // Píxel del mapa -> posición en el laberinto
+        let to_world = |u: f32, v: f32| {
+            if self.rotate {
+                let (du, dv) = ((u - center_x) / cell, (v - center_y) / cell);
+                (
+                    player.pos.x + du * cos - dv * sin,
+                    player.pos.y + du * sin + dv * cos,
+                )
+            } else {
+                (u / cell, v / cell)
+            }
+        };
+        // Posición en el laberinto -> píxel de la pantalla
+        let to_screen = |x: f32, y: f32| {
+            let (u, v) = if self.rotate {
+                let (dx, dy) = (x - player.pos.x, y - player.pos.y);
+                (
+                    center_x + (dx * cos + dy * sin) * cell,
+                    center_y + (-dx * sin + dy * cos) * cell,
+                )
+            } else {
+                (x * cell, y * cell)
+            };
+            (left as i32 + u as i32, top as i32 + v as i32)
+        };
+
+        for v in 0..height {
+            for u in 0..width {
+                let (x, y) = to_world(u as f32 + 0.5, v as f32 + 0.5);
+                framebuffer.point(left + u, top + v, self.color_at(maze, x, y));
+            }
+        }
+
+        framebuffer.draw_rect_outline(
+            left.saturating_sub(2),
+            top.saturating_sub(2),
+            width + 4,
+            height + 4,
+            2,
+            Color::from_hex(0x2B2407),
+        );
+
+        let inside = |(x, y): (i32, i32)| {
+            x >= left as i32
+                && y >= top as i32
+                && x < (left + width) as i32
+                && y < (top + height) as i32
+        };
+        let radius = (cell / 3.0).max(2.0) as i32;
+
+        if self.show_enemy {
+            let enemy = to_screen(sprite.x, sprite.y);
+            if inside(enemy) {
+                framebuffer.fill_circle(enemy.0, enemy.1, radius, Color::from_hex(0xE02020));
+            }
+        }
+
+        // Cono de visión: los dos bordes del campo de visión, hasta la pared
+        let origin = to_screen(player.pos.x, player.pos.y);
+        for edge in [-0.5, 0.5] {
+            let a = player.angle + player.fov * edge;
+            let hit = cast_rays(None, maze, doors, player, a, block_size);
+            let length = (hit.distance / block_size as f32).min(VIEW_CONE_LENGTH);
+            let end = to_screen(
+                player.pos.x + a.cos() * length,
+                player.pos.y + a.sin() * length,
+            );
+            framebuffer.draw_line(origin.0, origin.1, end.0, end.1, Color::from_hex(0xFFFFFF));
+        }
+
+        framebuffer.fill_circle(origin.0, origin.1, radius, Color::from_hex(0x5F88CC));
+        framebuffer.draw_circle(origin.0, origin.1, radius, Color::from_hex(0x1B2A44));
+    }
+}
@@ -1,3 +1,8 @@
+use crate::camera::CameraMotion;
+use crate::items::Inventory;
+use crate::lighting::Flashlight;
+use crate::vitals::{MAX_HEALTH, MAX_SANITY};
+
 pub struct Player {
     pub pos: Vec2,
     pub dir: Vec2,
@@ -5,6 +10,13 @@ pub struct Player {
     pub speed: f32,
     pub rotation_speed: f32,
     pub fov: f32,
+    pub inventory: Inventory,
+    pub health: f32,
+    pub sanity: f32,
+    /// Ticks que quedan sin poder recibir daño tras un golpe.
+    pub invulnerable: u32,
+    pub flashlight: Flashlight,
+    pub camera: CameraMotion,
 }
 
 impl Player {
@@ -20,6 +32,12 @@ impl Player {
             speed,
             rotation_speed: rotation_speed * 0.3,
             fov,
+            inventory: Inventory::default(),
+            health: MAX_HEALTH,
+            sanity: MAX_SANITY,
+            invulnerable: 0,
+            flashlight: Flashlight::default(),
+            camera: CameraMotion::default(),
         }
     }
 }
@@ -0,0 +1,177 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::lighting::noise;
+use crate::text::{measure_text, Align, TextStyle};
+use rayon::prelude::*;
+
+// Desplazamiento de los canales rojo y azul en los bordes, en píxeles con 1300
+// de ancho
+const ABERRATION: f32 = 4.0;
+
+// Cuánto oscurecen las líneas de barrido y cuántas hay en 900 filas
+const SCANLINE_DARKEN: f32 = 0.8;
+const SCANLINE_ROWS: usize = 450;
+
+// Amplitud del grano, en niveles de cada canal
+const GRAIN: f32 = 14.0;
+
+// Oscuridad de las esquinas
+const VIGNETTE: f32 = 1.1;
+
+// Gradación de cinta vieja: algo de color perdido, negros levantados y un
+// tinte verde amarillento
+const DESATURATION: f32 = 0.3;
+const BLACK_LEVEL: f32 = 14.0;
+const GRADE_TINT: (f32, f32, f32) = (1.03, 1.06, 0.84);
+
+// Ticks por segundo del reloj de la cámara y fecha que muestra
+const TICKS_PER_SECOND: u64 = 60;
+const DATE: &str = "OCT 19 1996";
+
+/// Efectos que se aplican a la vista 3D para que parezca grabada con una
+/// cámara de video vieja. Cada uno se enciende o apaga en la configuración.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostEffects {
+    pub scanlines: bool,
+    pub grain: bool,
+    pub vignette: bool,
+    pub chromatic_aberration: bool,
+    pub color_grading: bool,
+    pub timestamp: bool,
+}
+
+impl Default for PostEffects {
+    fn default() -> Self {
+        PostEffects {
+            scanlines: true,
+            grain: true,
+            vignette: true,
+            chromatic_aberration: true,
+            color_grading: true,
+            timestamp: true,
+        }
+    }
+}
+
+impl PostEffects {
+    // Si algún efecto cambia los píxeles de la imagen (el reloj solo se dibuja
+    // encima)
+    fn touches_pixels(&self) -> bool {
+        self.scanlines
+            || self.grain
+            || self.vignette
+            || self.chromatic_aberration
+            || self.color_grading
+    }
+}
+
+/// Aplica los efectos activos al cuadro ya dibujado, en orden: aberración
+/// cromática, gradación de color, viñeta, líneas de barrido, grano y, encima
+/// de todo, el indicador de grabación con la hora.
+pub fn apply(framebuffer: &mut Framebuffer, effects: &PostEffects, tick: u64) {
+    if effects.touches_pixels() {
+        apply_pixels(framebuffer, effects, tick);
+    }
+    if effects.timestamp {
+        draw_timestamp(framebuffer, tick);
+    }
+}
+
+// Cada fila se procesa por separado, así que las filas se reparten entre hilos
+fn apply_pixels(framebuffer: &mut Framebuffer, effects: &PostEffects, tick: u64) {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    let aberration = ABERRATION * width as f32 / 1300.0;
+    let scanline = (height / SCANLINE_ROWS).max(1);
+    let grain_seed = tick.wrapping_mul(0x9E37_79B9);
+
+    framebuffer
+        .buffer
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let source = effects.chromatic_aberration.then(|| row.to_vec());
+            let dy = y as f32 / height as f32 - 0.5;
+            let dark_line = effects.scanlines && (y / scanline) % 2 == 1;
+
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let dx = x as f32 / width as f32 - 0.5;
+                let (mut r, mut g, mut b) = channels(*pixel);
+
+                // Rojo y azul se separan hacia los bordes, como en una lente barata
+                if let Some(source) = &source {
+                    let shift = (dx * 2.0 * (dx * 2.0).abs() * aberration).round() as isize;
+                    let at = |offset: isize| {
+                        source[(x as isize + offset).clamp(0, width as isize - 1) as usize]
+                    };
+                    r = channels(at(shift)).0;
+                    b = channels(at(-shift)).2;
+                }
+
+                if effects.color_grading {
+                    let gray = 0.3 * r + 0.59 * g + 0.11 * b;
+                    let grade = |c: f32, tint: f32| {
+                        let c = (c + (gray - c) * DESATURATION) * tint;
+                        BLACK_LEVEL + c * (1.0 - BLACK_LEVEL / 255.0)
+                    };
+                    r = grade(r, GRADE_TINT.0);
+                    g = grade(g, GRADE_TINT.1);
+                    b = grade(b, GRADE_TINT.2);
+                }
+
+                let mut factor = 1.0;
+                if effects.vignette {
+                    factor *= (1.0 - VIGNETTE * (dx * dx + dy * dy)).max(0.0);
+                }
+                if dark_line {
+                    factor *= SCANLINE_DARKEN;
+                }
+                let grain = if effects.grain {
+                    (noise(grain_seed.wrapping_add((y * width + x) as u64)) - 0.5) * 2.0 * GRAIN
+                } else {
+                    0.0
+                };
+
+                let out = |c: f32| (c * factor + grain).clamp(0.0, 255.0) as u32;
+                *pixel = (out(r) << 16) | (out(g) << 8) | out(b);
+            }
+        });
+}
+
+fn channels(pixel: u32) -> (f32, f32, f32) {
+    (
+        ((pixel >> 16) & 0xFF) as f32,
+        ((pixel >> 8) & 0xFF) as f32,
+        (pixel & 0xFF) as f32,
+    )
+}
+
+// `REC` con un punto rojo que parpadea cada medio segundo arriba a la
+// derecha, y debajo la fecha y el tiempo grabado
+fn draw_timestamp(framebuffer: &mut Framebuffer, tick: u64) {
+    let style = TextStyle::new(framebuffer.text_size(30.0), Color::from_hex(0xF2F2E6))
+        .align(Align::Right)
+        .shadow(Color::from_hex(0x000000));
+    let line = style.line_height();
+    let right = framebuffer.width.saturating_sub(line);
+    let mut y = line / 2;
+
+    let rec_width = measure_text("REC", &style).0;
+    if (tick / (TICKS_PER_SECOND / 2)).is_multiple_of(2) {
+        let radius = (line / 5).max(2) as i32;
+        let cx = right.saturating_sub(rec_width) as i32 - radius * 2;
+        let cy = (y + line / 2) as i32;
+        framebuffer.fill_circle(cx, cy, radius, Color::from_hex(0xE02020));
+    }
+    y += framebuffer.draw_text_styled("REC", right, y, &style);
+
+    let seconds = tick / TICKS_PER_SECOND;
+    let clock = format!(
+        "{}  {:02}:{:02}:{:02}",
+        DATE,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    framebuffer.draw_text_styled(&clock, right, y, &style);
+}
@@ -0,0 +1,629 @@
+use crate::camera::View;
+use crate::cast_ray::{cast_column, cast_rays, Intersect};
+use crate::color::Color;
+use crate::doors::{Doors, DOOR, LOCKED_DOOR, SWITCH, SWITCH_DOOR};
+use crate::framebuffer::Framebuffer;
+use crate::items::Items;
+use crate::lighting::Lighting;
+use crate::lightmap::Lightmap;
+use crate::maze::{
+    is_transparent, thin_wall, wall_span, FENCE, HALF_WALL, LOW_CEILING, OPEN_SKY, PILLAR, WINDOW,
+};
+use crate::minimap::Minimap;
+use crate::player::Player;
+use crate::sky;
+use crate::sprite::Sprite;
+use crate::texture::Texture;
+use crate::vitals::{INVULNERABLE_TICKS, MAX_HEALTH, MAX_SANITY};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+static WALL1: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(crate::load_texture("WALL.jpg")));
+static WINDOW_TEXTURE: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(window_texture()));
+static FENCE_TEXTURE: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(fence_texture()));
+
+// Rayos mínimos por tarea al repartir el lanzamiento entre hilos
+const RAYS_PER_TASK: usize = 32;
+
+// Distancia (en celdas) que se retrocede desde una pared para leer su luz en
+// el lightmap, cuyas muestras dentro de las paredes están a oscuras
+const WALL_LIGHT_OFFSET: f32 = 0.05;
+
+// Rayos dibujados en la vista cenital (uno cada pocas columnas basta)
+const TOP_DOWN_RAYS: usize = 60;
+
+/// Carga las texturas de las paredes, que si no se cargarían con el primer
+/// cuadro.
+pub fn load_textures() {
+    Lazy::force(&WALL1);
+}
+
+/// Lo que se dibuja de una partida.
+pub struct Scene<'a> {
+    pub player: &'a Player,
+    pub maze: &'a [Vec<char>],
+    pub doors: &'a Doors,
+    pub items: &'a Items,
+    pub lightmap: &'a Lightmap,
+    pub sprite: &'a Sprite,
+    pub minimap: &'a Minimap,
+    /// Balanceo y temblor de la cámara en este cuadro.
+    pub view: View,
+    /// Ticks de simulación transcurridos, para las animaciones.
+    pub tick: u64,
+}
+
+/// Tiempo acumulado en cada etapa del render de la vista 3D.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageTimes {
+    pub floor: Duration,
+    pub rays: Duration,
+    pub walls: Duration,
+    pub sprites: Duration,
+    pub post: Duration,
+    pub hud: Duration,
+}
+
+// Ejecuta `f` sumando lo que tarda en `slot`
+pub fn timed<T>(slot: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *slot += start.elapsed();
+    result
+}
+
+pub fn render3d(framebuffer: &mut Framebuffer, scene: &Scene, times: &mut StageTimes) {
+    let block_size = 100;
+    let Scene {
+        player,
+        maze,
+        doors,
+        items,
+        lightmap,
+        sprite,
+        minimap,
+        view,
+        tick,
+    } = *scene;
+
+    // Fila del horizonte, que el balanceo y el temblor mueven
+    let horizon = framebuffer.height as f32 * (0.5 + view.pitch);
+    let lighting = Lighting::new(
+        framebuffer.width,
+        framebuffer.height,
+        player,
+        lightmap,
+        tick,
+    );
+
+    timed(&mut times.floor, || {
+        render_floor_and_ceiling(framebuffer, player, maze, &lighting, horizon)
+    });
+    let columns = timed(&mut times.rays, || {
+        cast_all_rays(framebuffer.width, player, maze, doors, block_size)
+    });
+    timed(&mut times.walls, || {
+        render_walls(framebuffer, &columns, &lighting, horizon, block_size)
+    });
+
+    // Los objetos se esconden detrás de cualquier pared que salga del suelo,
+    // salvo los vidrios y las rejas, que se ven delante de ellos
+    let depth: Vec<f32> = columns
+        .iter()
+        .map(|hits| {
+            hits.iter()
+                .find(|hit| hit.span.bottom <= 0.0 && !is_transparent(hit.cell))
+                .map_or(f32::INFINITY, |hit| hit.distance)
+        })
+        .collect();
+
+    // Renderizar los objetos y el sprite
+    timed(&mut times.sprites, || {
+        items.render(framebuffer, player, &depth, &lighting, horizon, block_size);
+        sprite.render(framebuffer, player, &lighting, horizon);
+    });
+    timed(&mut times.post, || {
+        shift_view(framebuffer, view.shift);
+        render_distortion(framebuffer, player, tick)
+    });
+
+    // Renderizar el mini-mapa
+    timed(&mut times.hud, || {
+        minimap.render(framebuffer, player, maze, doors, sprite, block_size)
+    });
+}
+
+// Cada fila del suelo (y del techo) está a una distancia fija: la de la pared
+// cuya base (o cuyo borde superior) cae en esa fila. Las luces del nivel se
+// ven como paneles en el techo, y sobre las celdas a cielo abierto se ve el
+// cielo, que no recibe luz ni niebla.
+fn render_floor_and_ceiling(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    maze: &[Vec<char>],
+    lighting: &Lighting,
+    horizon: f32,
+) {
+    let ceiling_color = 0x88814a;
+    let floor_color = 0x58450e;
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    let open_sky = |(x, y): (f32, f32)| {
+        x >= 0.0
+            && y >= 0.0
+            && maze
+                .get(y as usize)
+                .and_then(|row| row.get(x as usize))
+                .is_some_and(|&cell| cell == OPEN_SKY)
+    };
+
+    framebuffer
+        .buffer
+        .par_chunks_mut(framebuffer.width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let ceiling = (y as f32 + 0.5) < horizon;
+            let color = if ceiling { ceiling_color } else { floor_color };
+            let from_horizon = (y as f32 + 0.5 - horizon).abs();
+            let distance = height / (4.0 * from_horizon);
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let point = lighting.point(x, distance);
+                if ceiling && open_sky(point) {
+                    let angle = player.angle - player.fov / 2.0 + player.fov * x as f32 / width;
+                    *pixel = sky::pixel(angle, from_horizon, height / 2.0);
+                    continue;
+                }
+                let panel = if ceiling {
+                    lighting.panel(point, distance)
+                } else {
+                    None
+                };
+                *pixel = panel.unwrap_or_else(|| lighting.shade(color, point, distance, x, y));
+            }
+        });
+}
+
+// Un rayo por columna de la pantalla; cada hilo lanza un rango de columnas.
+// `collect` conserva el orden, así que el resultado no depende de los hilos.
+fn cast_all_rays(
+    num_rays: usize,
+    player: &Player,
+    maze: &[Vec<char>],
+    doors: &Doors,
+    block_size: usize,
+) -> Vec<Vec<Intersect>> {
+    // Los rayos siguen de largo tras las paredes normales solo si hay paredes
+    // más altas que podrían asomar por encima
+    let max_height = maze
+        .iter()
+        .flatten()
+        .filter_map(|&cell| wall_span(cell))
+        .fold(1.0, |max, span| span.top.max(max));
+
+    (0..num_rays)
+        .into_par_iter()
+        .with_min_len(RAYS_PER_TASK)
+        .map(|i| {
+            let current_ray = i as f32 / num_rays as f32;
+            let a = player.angle - (player.fov / 2.0) + (player.fov * current_ray);
+            cast_column(maze, doors, player, a, max_height, block_size)
+        })
+        .collect()
+}
+
+// El buffer se guarda por filas, así que cada hilo dibuja una franja de filas
+// completa con los tramos de pared ya calculados por columna: los píxeles son
+// los mismos que dibujando columna por columna en un solo hilo.
+//
+// En cada píxel se busca la superficie opaca más cercana (o se deja el suelo o
+// el techo) y encima se componen, de atrás hacia adelante, los vidrios y las
+// rejas que hay delante.
+fn render_walls(
+    framebuffer: &mut Framebuffer,
+    columns: &[Vec<Intersect>],
+    lighting: &Lighting,
+    horizon: f32,
+    block_size: usize,
+) {
+    let hh = framebuffer.height as f32 / 2.0;
+
+    framebuffer
+        .buffer
+        .par_chunks_mut(framebuffer.width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let from_horizon = y as f32 + 0.5 - horizon;
+            for (i, (pixel, hits)) in row.iter_mut().zip(columns).enumerate() {
+                let surface = |hit: &Intersect| {
+                    surface_at(hit, from_horizon, hh, (i, y), lighting, block_size)
+                };
+                let opaque = hits
+                    .iter()
+                    .enumerate()
+                    .find_map(|(index, hit)| match surface(hit) {
+                        Some((color, 255)) => Some((index, color)),
+                        _ => None,
+                    });
+                let (end, mut color) = opaque.unwrap_or((hits.len(), *pixel));
+                for hit in hits[..end].iter().rev() {
+                    if let Some((layer, alpha)) = surface(hit) {
+                        color = blend(color, layer, alpha);
+                    }
+                }
+                *pixel = color;
+            }
+        });
+}
+
+// Color y opacidad del píxel (x, y), a `from_horizon` filas del horizonte, en
+// el tramo `hit`, si lo cubre: su cara o su tapa (encima de una pared baja o
+// debajo de una viga). A `distance` celdas una pared mide `hh / distance`
+// píxeles, así que en esa fila se ve la altura 0.5 - from_horizon * distance / hh.
+fn surface_at(
+    hit: &Intersect,
+    from_horizon: f32,
+    hh: f32,
+    (x, y): (usize, usize),
+    lighting: &Lighting,
+    block_size: usize,
+) -> Option<(u32, u8)> {
+    let near = hit.distance / block_size as f32;
+    let far = hit.exit / block_size as f32;
+    let span = hit.span;
+
+    // Dentro de una celda de techo bajo no se ve la cara de la viga
+    if hit.distance > 0.0 {
+        let height = 0.5 - from_horizon * near / hh;
+        if span.bottom <= height && height < span.top {
+            let ty = ((span.top - height).rem_euclid(1.0) * 512.0) as usize;
+            // La luz de la pared es la del suelo justo delante de ella
+            let point = lighting.point(x, near - WALL_LIGHT_OFFSET);
+            let (pixel, alpha) = wall_texel(hit.cell, hit.tx, ty);
+            return Some((lighting.shade(pixel, point, near, x, y), alpha));
+        }
+    }
+
+    let cap = if span.top < 0.5 && from_horizon > 0.0 {
+        Some(span.top)
+    } else if span.bottom > 0.5 && from_horizon < 0.0 {
+        Some(span.bottom)
+    } else {
+        None
+    };
+    let distance = hh * (0.5 - cap?) / from_horizon;
+    if !(near..far).contains(&distance) {
+        return None;
+    }
+    let point = lighting.point(x, distance);
+    let tx = (point.0.fract() * 512.0) as usize;
+    let ty = (point.1.fract() * 512.0) as usize;
+    Some((
+        lighting.shade(WALL1.get_pixel(tx, ty), point, distance, x, y),
+        255,
+    ))
+}
+
+// Mezcla `layer` sobre `pixel` según su opacidad
+fn blend(pixel: u32, layer: u32, alpha: u8) -> u32 {
+    let alpha = alpha as u32;
+    let channel = |shift: u32| {
+        let under = (pixel >> shift) & 0xFF;
+        let over = (layer >> shift) & 0xFF;
+        (over * alpha + under * (255 - alpha)) / 255
+    };
+    (channel(16) << 16) | (channel(8) << 8) | channel(0)
+}
+
+// Píxel de la textura de la pared de `cell` y su opacidad
+fn wall_texel(cell: char, tx: usize, ty: usize) -> (u32, u8) {
+    match cell {
+        WINDOW => (
+            WINDOW_TEXTURE.get_pixel(tx, ty),
+            WINDOW_TEXTURE.get_alpha(tx, ty),
+        ),
+        FENCE => (
+            FENCE_TEXTURE.get_pixel(tx, ty),
+            FENCE_TEXTURE.get_alpha(tx, ty),
+        ),
+        _ => (wall_pixel(cell, tx, ty), 255),
+    }
+}
+
+// Ventana: marco y travesaños opacos y un vidrio verdoso casi transparente,
+// con un reflejo en diagonal
+fn window_texture() -> Texture {
+    Texture::from_fn(512, 512, |x, y| {
+        let frame = |c: usize| !(32..480).contains(&c) || (248..264).contains(&c);
+        if frame(x) || frame(y) {
+            (0x8A8470, 255)
+        } else if (120..160).contains(&((x + y) % 512)) {
+            (0xD8F0F4, 110)
+        } else {
+            (0xA8D0DC, 70)
+        }
+    })
+}
+
+// Reja de alambre en rombos entre dos caños, con huecos del todo transparentes
+fn fence_texture() -> Texture {
+    Texture::from_fn(512, 512, |x, y| {
+        let wire = |c: usize| c % 64 < 4;
+        if !(24..488).contains(&y) {
+            (0x707070, 255)
+        } else if wire(x + y) || wire(x + 512 - y) {
+            (0x9A9A9A, 255)
+        } else {
+            (0x000000, 0)
+        }
+    })
+}
+
+// Textura de pared teñida según el tipo de celda. Los interruptores son una
+// placa en el centro de la pared.
+fn wall_pixel(cell: char, tx: usize, ty: usize) -> u32 {
+    let pixel = WALL1.get_pixel(tx, ty);
+    match cell {
+        DOOR => tint(pixel, (0.8, 0.55, 0.35)),
+        LOCKED_DOOR => tint(pixel, (0.9, 0.35, 0.3)),
+        SWITCH_DOOR => tint(pixel, (0.45, 0.6, 0.9)),
+        SWITCH if (192..320).contains(&tx) && (160..352).contains(&ty) => {
+            if (224..288).contains(&tx) && (192..320).contains(&ty) {
+                0x4CAF50
+            } else {
+                0x303030
+            }
+        }
+        _ => pixel,
+    }
+}
+
+fn tint(pixel: u32, (r, g, b): (f32, f32, f32)) -> u32 {
+    let channel = |shift: u32, factor: f32| (((pixel >> shift) & 0xFF) as f32 * factor) as u32;
+    (channel(16, r) << 16) | (channel(8, g) << 8) | channel(0, b)
+}
+
+// Corre la imagen `shift` (en fracciones del alto) hacia la derecha,
+// repitiendo la columna del borde en el hueco que queda
+fn shift_view(framebuffer: &mut Framebuffer, shift: f32) {
+    let offset = (shift * framebuffer.height as f32).round() as isize;
+    if offset == 0 {
+        return;
+    }
+    let width = framebuffer.width;
+    framebuffer.buffer.par_chunks_mut(width).for_each(|row| {
+        let source = row.to_vec();
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let sx = (x as isize - offset).clamp(0, width as isize - 1) as usize;
+            *pixel = source[sx];
+        }
+    });
+}
+
+// Con poca cordura las filas ondulan y el color se apaga; tras un golpe la
+// imagen se tiñe de rojo y con poca salud los bordes laten en rojo.
+fn render_distortion(framebuffer: &mut Framebuffer, player: &Player, tick: u64) {
+    let madness = (1.0 - player.sanity / (MAX_SANITY * 0.5)).clamp(0.0, 1.0);
+    let flash = player.invulnerable as f32 / INVULNERABLE_TICKS as f32 * 0.5;
+    let wounded = (1.0 - player.health / (MAX_HEALTH * 0.35)).clamp(0.0, 1.0);
+    let pulse = wounded * (0.25 + 0.15 * (tick as f32 * 0.1).sin());
+    if madness <= 0.0 && flash <= 0.0 && pulse <= 0.0 {
+        return;
+    }
+
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    let amplitude = madness * width as f32 * 0.015;
+    let time = tick as f32;
+
+    framebuffer
+        .buffer
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let shift = (amplitude * (y as f32 * 0.04 + time * 0.08).sin()).round() as isize;
+            let source = row.to_vec();
+            let dy = y as f32 / height as f32 - 0.5;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let sx = (x as isize - shift).clamp(0, width as isize - 1) as usize;
+                let dx = x as f32 / width as f32 - 0.5;
+                let edge = ((dx * dx + dy * dy) * 2.0).min(1.0);
+                let red = flash.max(pulse * edge);
+                *pixel = shade(source[sx], madness * 0.6, red);
+            }
+        });
+}
+
+// Quita saturación y mezcla hacia rojo
+fn shade(pixel: u32, desaturate: f32, red: f32) -> u32 {
+    let r = ((pixel >> 16) & 0xFF) as f32;
+    let g = ((pixel >> 8) & 0xFF) as f32;
+    let b = (pixel & 0xFF) as f32;
+    let gray = 0.3 * r + 0.59 * g + 0.11 * b;
+    let mix = |c: f32| c + (gray - c) * desaturate;
+    let (r, g, b) = (mix(r), mix(g), mix(b));
+    let r = r + (255.0 - r) * red;
+    let g = g * (1.0 - red);
+    let b = b * (1.0 - red);
+    ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+/// Vista cenital a pantalla completa para depurar: el laberinto entero, los
+/// rayos del campo de visión del jugador, el monstruo y el camino que seguirá
+/// hacia el jugador.
+pub fn render_top_down(framebuffer: &mut Framebuffer, scene: &Scene) {
+    let block_size = 100;
+    let Scene {
+        player,
+        maze,
+        doors,
+        items,
+        lightmap,
+        sprite,
+        ..
+    } = *scene;
+    let rows = maze.len();
+    let cols = maze.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+    // Celdas cuadradas lo más grandes posible, centradas en la pantalla
+    let cell = (framebuffer.width / cols)
+        .min(framebuffer.height / rows)
+        .max(1);
+    let x_offset = framebuffer.width.saturating_sub(cols * cell) / 2;
+    let y_offset = framebuffer.height.saturating_sub(rows * cell) / 2;
+    let to_screen = |x: f32, y: f32| {
+        (
+            x_offset as i32 + (x * cell as f32) as i32,
+            y_offset as i32 + (y * cell as f32) as i32,
+        )
+    };
+
+    framebuffer.fill_rect(0, 0, framebuffer.width, framebuffer.height, 0x000000);
+    for (row, cells) in maze.iter().enumerate() {
+        for (col, &c) in cells.iter().enumerate() {
+            draw_cell(
+                framebuffer,
+                x_offset + col * cell,
+                y_offset + row * cell,
+                cell,
+                c,
+            );
+        }
+    }
+    // Las paredes delgadas son una línea sobre el suelo de su celda
+    for (row, cells) in maze.iter().enumerate() {
+        for (col, &c) in cells.iter().enumerate() {
+            if let Some(segment) = thin_wall(c, col, row) {
+                let (x0, y0) = to_screen(segment.from.0, segment.from.1);
+                let (x1, y1) = to_screen(segment.to.0, segment.to.1);
+                framebuffer.draw_line(x0, y0, x1, y1, Color::from_hex(cell_color('-')));
+            }
+        }
+    }
+
+    // Rayos del campo de visión hasta la pared que tocan
+    let (px, py) = to_screen(player.pos.x, player.pos.y);
+    for i in 0..=TOP_DOWN_RAYS {
+        let a = player.angle - player.fov / 2.0 + player.fov * i as f32 / TOP_DOWN_RAYS as f32;
+        let hit = cast_rays(None, maze, doors, player, a, block_size);
+        let distance = hit.distance / block_size as f32;
+        let (hx, hy) = to_screen(
+            player.pos.x + a.cos() * distance,
+            player.pos.y + a.sin() * distance,
+        );
+        framebuffer.draw_line(px, py, hx, hy, Color::from_hex(0xFF33DD));
+    }
+
+    // Camino previsto del monstruo y, si ve al jugador, la línea de visión
+    let path = sprite.predicted_path(player, maze, block_size, 600, 10);
+    for step in path.windows(2) {
+        let (x0, y0) = to_screen(step[0].0, step[0].1);
+        let (x1, y1) = to_screen(step[1].0, step[1].1);
+        framebuffer.draw_line(x0, y0, x1, y1, Color::from_hex(0xFF8800));
+    }
+    let (sx, sy) = to_screen(sprite.x, sprite.y);
+    if sprite.sees_player(player, maze, doors, block_size) {
+        framebuffer.draw_line(sx, sy, px, py, Color::from_hex(0xFF0000));
+    }
+
+    let radius = (cell / 3).max(2) as i32;
+    for light in lightmap.iter() {
+        let (lx, ly) = to_screen(light.x, light.y);
+        let color = Color::from_hex(light.source.color);
+        framebuffer.draw_circle(lx, ly, (light.source.radius * cell as f32) as i32, color);
+        framebuffer.fill_circle(lx, ly, (radius / 2).max(1), color);
+    }
+    for item in items.iter() {
+        let (ix, iy) = to_screen(item.x, item.y);
+        let color = Color::from_hex(item.kind.color());
+        framebuffer.fill_circle(ix, iy, (radius / 2).max(1), color);
+    }
+    framebuffer.fill_circle(sx, sy, radius, Color::from_hex(0xE02020));
+    framebuffer.draw_circle(sx, sy, radius, Color::from_hex(0x000000));
+
+    let heading = cell as f32 * 1.5;
+    framebuffer.draw_line(
+        px,
+        py,
+        px + (player.angle.cos() * heading) as i32,
+        py + (player.angle.sin() * heading) as i32,
+        Color::from_hex(0xFFFFFF),
+    );
+    framebuffer.fill_circle(px, py, radius, Color::from_hex(0x5F88CC));
+    framebuffer.draw_circle(px, py, radius, Color::from_hex(0x1B2A44));
+}
+
+/// Color de cada tipo de celda en los mapas 2D. Las celdas con una pared
+/// delgada tienen el color del suelo; la pared se dibuja aparte.
+pub fn cell_color(cell: char) -> u32 {
+    match cell {
+        '+' => 0xD6C34E,
+        '-' => 0xBFAA25,
+        '|' => 0xB9AB53,
+        'g' => 0xc92828,
+        ' ' => 0x7F5A1B,
+        DOOR => 0x8A5A2B,
+        LOCKED_DOOR => 0xA0302A,
+        SWITCH_DOOR => 0x3A6EA5,
+        SWITCH => 0x4CAF50,
+        PILLAR => 0xE8D86A,
+        HALF_WALL => 0x9C8A3A,
+        LOW_CEILING => 0x6A4A16,
+        OPEN_SKY => 0x6E7A80,
+        WINDOW => 0x9EC8D8,
+        FENCE => 0x8C8C8C,
+        c if thin_wall(c, 0, 0).is_some() => cell_color(' '),
+        _ => 0x000000,
+    }
+}
+
+fn draw_cell(framebuffer: &mut Framebuffer, x: usize, y: usize, block_size: usize, cell: char) {
+    framebuffer.fill_rect(x, y, block_size, block_size, cell_color(cell));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Difficulty;
+    use crate::postfx::{self, PostEffects};
+
+    // Un cuadro fijo de un nivel generado, renderizado con `threads` hilos
+    fn render_frame(threads: usize) -> Vec<u32> {
+        let (player, sprite, maze, doors, items, lightmap) = crate::new_run(
+            "generated 6x6",
+            3,
+            Difficulty::Normal,
+            std::f32::consts::PI / 3.0,
+        )
+        .unwrap();
+        let minimap = Minimap::new(&maze);
+        let scene = Scene {
+            player: &player,
+            maze: &maze,
+            doors: &doors,
+            items: &items,
+            lightmap: &lightmap,
+            sprite: &sprite,
+            minimap: &minimap,
+            view: View::default(),
+            tick: 30,
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            let mut framebuffer = Framebuffer::new(160, 120);
+            render3d(&mut framebuffer, &scene, &mut StageTimes::default());
+            postfx::apply(&mut framebuffer, &PostEffects::default(), scene.tick);
+            framebuffer.buffer
+        })
+    }
+
+    #[test]
+    fn frames_do_not_depend_on_the_thread_count() {
+        assert_eq!(render_frame(1), render_frame(4));
+    }
+}
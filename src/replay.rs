@@ -0,0 +1,293 @@
+use crate::cli::Difficulty;
+use crate::input::InputFrame;
+use crate::items::ItemKind;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+// Cabecera de cada versión del formato, de la actual a la más vieja, cuántas
+// columnas tiene cada tick y si guarda la dificultad. A las versiones viejas
+// les faltan las últimas columnas, que toman su valor por defecto; la v1 no
+// tenía dificultad y se jugaba en normal.
+const HEADERS: [(&str, usize, bool); 5] = [
+    ("# The B∀CKROOMS replay v5", 8, true),
+    ("# The B∀CKROOMS replay v4", 7, true),
+    ("# The B∀CKROOMS replay v3", 6, true),
+    ("# The B∀CKROOMS replay v2", 5, true),
+    ("# The B∀CKROOMS replay v1", 5, false),
+];
+
+const HEADER_PREFIX: &str = "# The B∀CKROOMS replay v";
+
+/// Grabación de una partida: la semilla, el nivel, la dificultad y la entrada
+/// de cada tick.
+///
+/// Como la simulación avanza un paso fijo por tick, volver a aplicar los mismos
+/// `InputFrame` sobre el mismo nivel reproduce la partida cuadro a cuadro.
+pub struct Replay {
+    pub seed: u64,
+    pub level: String,
+    pub difficulty: Difficulty,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, level: &str, difficulty: Difficulty) -> Self {
+        Replay {
+            seed,
+            level: level.to_string(),
+            difficulty,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, input: InputFrame) {
+        self.frames.push(input);
+    }
+
+    // Formato de texto: una cabecera, `seed`, `level`, `difficulty` y luego una línea por tick
+    // con `adelante atrás izquierda derecha giro_del_ratón interactuar objeto linterna`,
+    // donde `objeto` es el carácter del objeto usado o `-`.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADERS[0].0)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        writeln!(writer, "level {}", self.level)?;
+        writeln!(writer, "difficulty {}", self.difficulty.name())?;
+        writeln!(writer, "frames {}", self.frames.len())?;
+        for frame in &self.frames {
+            writeln!(
+                writer,
+                "{} {} {} {} {} {} {} {}",
+                frame.forward as u8,
+                frame.backward as u8,
+                frame.turn_left as u8,
+                frame.turn_right as u8,
+                frame.mouse_turn,
+                frame.interact as u8,
+                frame.use_item.map_or('-', ItemKind::cell),
+                frame.toggle_flashlight as u8
+            )?;
+        }
+        writer.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Replay> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines();
+
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let (_, fields, has_difficulty) = *HEADERS
+            .iter()
+            .find(|(name, ..)| header == *name)
+            .ok_or_else(|| match header.strip_prefix(HEADER_PREFIX) {
+                Some(version) => invalid_data(&format!("unsupported replay version v{}", version)),
+                None => invalid_data("missing replay header"),
+            })?;
+
+        let seed = header_value(&mut lines, "seed")?
+            .parse()
+            .map_err(|_| invalid_data("invalid seed"))?;
+        let level = header_value(&mut lines, "level")?;
+        let difficulty = if has_difficulty {
+            Difficulty::from_name(&header_value(&mut lines, "difficulty")?)
+                .ok_or_else(|| invalid_data("invalid difficulty"))?
+        } else {
+            Difficulty::Normal
+        };
+        let count: usize = header_value(&mut lines, "frames")?
+            .parse()
+            .map_err(|_| invalid_data("invalid frame count"))?;
+
+        let mut frames = Vec::with_capacity(count);
+        for line in lines {
+            let line = line?;
+            if !line.trim().is_empty() {
+                frames.push(parse_frame(&line, fields)?);
+            }
+        }
+
+        if frames.len() != count {
+            return Err(invalid_data("frame count does not match header"));
+        }
+
+        Ok(Replay {
+            seed,
+            level,
+            difficulty,
+            frames,
+        })
+    }
+}
+
+/// Cursor sobre un replay cargado que entrega un `InputFrame` por tick.
+pub struct Playback {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback { replay, cursor: 0 }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn next_frame(&mut self) -> Option<InputFrame> {
+        let frame = self.replay.frames.get(self.cursor).copied();
+        self.cursor += 1;
+        frame
+    }
+}
+
+fn header_value(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    key: &str,
+) -> io::Result<String> {
+    let line = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| invalid_data("truncated replay header"))?;
+
+    match line.split_once(' ') {
+        Some((k, value)) if k == key => Ok(value.to_string()),
+        _ => Err(invalid_data(&format!(
+            "expected `{}` in replay header",
+            key
+        ))),
+    }
+}
+
+fn parse_frame(line: &str, count: usize) -> io::Result<InputFrame> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != count {
+        return Err(invalid_data("malformed replay frame"));
+    }
+
+    let flag = |s: &str| match s {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(invalid_data("malformed replay frame")),
+    };
+
+    Ok(InputFrame {
+        forward: flag(fields[0])?,
+        backward: flag(fields[1])?,
+        turn_left: flag(fields[2])?,
+        turn_right: flag(fields[3])?,
+        mouse_turn: fields[4]
+            .parse()
+            .map_err(|_| invalid_data("malformed replay frame"))?,
+        interact: match fields.get(5) {
+            Some(field) => flag(field)?,
+            None => false,
+        },
+        use_item: match fields.get(6) {
+            Some(&"-") | None => None,
+            Some(field) => Some(
+                field
+                    .chars()
+                    .next()
+                    .and_then(ItemKind::from_cell)
+                    .ok_or_else(|| invalid_data("malformed replay frame"))?,
+            ),
+        },
+        toggle_flashlight: match fields.get(7) {
+            Some(field) => flag(field)?,
+            None => false,
+        },
+    })
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_replays_load_back() {
+        let mut replay = Replay::new(1234, "generated 5x5", Difficulty::Easy);
+        replay.record(InputFrame {
+            forward: true,
+            mouse_turn: -0.25,
+            ..InputFrame::default()
+        });
+        replay.record(InputFrame {
+            interact: true,
+            use_item: Some(ItemKind::Battery),
+            toggle_flashlight: true,
+            ..InputFrame::default()
+        });
+
+        let path = std::env::temp_dir().join("backrooms-replay-roundtrip.replay");
+        let path = path.to_str().unwrap();
+        replay.save(path).unwrap();
+        let loaded = Replay::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.level, replay.level);
+        assert_eq!(loaded.difficulty, replay.difficulty);
+        assert_eq!(loaded.frames, replay.frames);
+    }
+
+    // Carga un replay escrito a mano con la cabecera `header` y un tick
+    fn load_text(name: &str, header: &str, frame: &str) -> io::Result<Replay> {
+        let path = std::env::temp_dir().join(format!("backrooms-replay-{}.replay", name));
+        let path = path.to_str().unwrap();
+        let difficulty = if header.ends_with("v1") {
+            ""
+        } else {
+            "difficulty hard\n"
+        };
+        let text = format!(
+            "{}\nseed 9\nlevel maze.txt\n{}frames 1\n{}\n",
+            header, difficulty, frame
+        );
+        std::fs::write(path, text).unwrap();
+        let replay = Replay::load(path);
+        std::fs::remove_file(path).unwrap();
+        replay
+    }
+
+    #[test]
+    fn loads_every_replay_version() {
+        let versions = [
+            ("v5", "1 0 0 1 0.5 1 b 1"),
+            ("v4", "1 0 0 1 0.5 1 b"),
+            ("v3", "1 0 0 1 0.5 1"),
+            ("v2", "1 0 0 1 0.5"),
+            ("v1", "1 0 0 1 0.5"),
+        ];
+        for (index, (version, frame)) in versions.into_iter().enumerate() {
+            let header = format!("{}{}", HEADER_PREFIX, &version[1..]);
+            let replay = load_text(version, &header, frame).unwrap();
+            let input = replay.frames[0];
+            assert_eq!(replay.seed, 9);
+            assert!(input.forward && input.turn_right && !input.backward);
+            assert_eq!(input.mouse_turn, 0.5);
+            assert_eq!(input.interact, index <= 2, "{}", version);
+            assert_eq!(input.use_item.is_some(), index <= 1, "{}", version);
+            assert_eq!(input.toggle_flashlight, index == 0, "{}", version);
+            let difficulty = if version == "v1" {
+                Difficulty::Normal
+            } else {
+                Difficulty::Hard
+            };
+            assert_eq!(replay.difficulty, difficulty);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_replay_versions() {
+        let error = load_text("v9", "# The B∀CKROOMS replay v9", "1 0 0 1 0.5")
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "unsupported replay version v9");
+        assert!(load_text("junk", "not a replay", "1 0 0 1 0.5").is_err());
+    }
+}
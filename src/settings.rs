@@ -0,0 +1,449 @@
+use crate::framebuffer::Filter;
+use crate::postfx::PostEffects;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+pub const SETTINGS_PATH: &str = "./settings.cfg";
+
+/// Opciones ajustables desde el menú de pausa, guardadas en `settings.cfg`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub fov_degrees: f32,
+    pub mouse_sensitivity: f32,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub resolution_scale: f32,
+    pub upscale_filter: Filter,
+    /// El mini-mapa gira con el jugador.
+    pub minimap_rotation: bool,
+    /// El mini-mapa muestra al monstruo.
+    pub minimap_enemy: bool,
+    /// Efectos de posproceso encendidos.
+    pub scanlines: bool,
+    pub film_grain: bool,
+    pub vignette: bool,
+    pub chromatic_aberration: bool,
+    pub color_grading: bool,
+    pub timestamp: bool,
+    /// Intensidad del balanceo al caminar y del temblor de la cámara (0 = sin efecto).
+    pub head_bob: f32,
+    pub screen_shake: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fov_degrees: 60.0,
+            mouse_sensitivity: 0.003,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            resolution_scale: 1.0,
+            upscale_filter: Filter::Nearest,
+            minimap_rotation: false,
+            minimap_enemy: false,
+            scanlines: true,
+            film_grain: true,
+            vignette: true,
+            chromatic_aberration: true,
+            color_grading: true,
+            timestamp: true,
+            head_bob: 1.0,
+            screen_shake: 1.0,
+        }
+    }
+}
+
+/// Una opción del menú: su clave en `settings.cfg`, su nombre y cómo leer y
+/// cambiar el campo que la guarda.
+struct Entry {
+    key: &'static str,
+    name: &'static str,
+    kind: Kind,
+}
+
+enum Kind {
+    Toggle {
+        get: fn(&Settings) -> bool,
+        set: fn(&mut Settings, bool),
+    },
+    Filter {
+        get: fn(&Settings) -> Filter,
+        set: fn(&mut Settings, Filter),
+    },
+    /// Número entre `min` y `max` que cambia de a `step`.
+    Range {
+        get: fn(&Settings) -> f32,
+        set: fn(&mut Settings, f32),
+        min: f32,
+        max: f32,
+        step: f32,
+        unit: Unit,
+    },
+}
+
+/// Cómo se muestra un número en el menú.
+#[derive(Clone, Copy)]
+enum Unit {
+    Degrees,
+    /// Milésimas, para la sensibilidad del ratón.
+    Thousandths,
+    Percent,
+}
+
+const fn toggle(
+    key: &'static str,
+    name: &'static str,
+    get: fn(&Settings) -> bool,
+    set: fn(&mut Settings, bool),
+) -> Entry {
+    Entry {
+        key,
+        name,
+        kind: Kind::Toggle { get, set },
+    }
+}
+
+// `limits` es (mínimo, máximo, paso)
+const fn range(
+    key: &'static str,
+    name: &'static str,
+    get: fn(&Settings) -> f32,
+    set: fn(&mut Settings, f32),
+    (min, max, step): (f32, f32, f32),
+    unit: Unit,
+) -> Entry {
+    Entry {
+        key,
+        name,
+        kind: Kind::Range {
+            get,
+            set,
+            min,
+            max,
+            step,
+            unit,
+        },
+    }
+}
+
+const PERCENT: (f32, f32, f32) = (0.0, 1.0, 0.1);
+
+// Las opciones en el orden del menú
+const ENTRIES: [Entry; 17] = [
+    range(
+        "fov_degrees",
+        "FOV",
+        |s| s.fov_degrees,
+        |s, v| s.fov_degrees = v,
+        (40.0, 100.0, 5.0),
+        Unit::Degrees,
+    ),
+    range(
+        "mouse_sensitivity",
+        "Mouse sensitivity",
+        |s| s.mouse_sensitivity,
+        |s, v| s.mouse_sensitivity = v,
+        (0.0005, 0.01, 0.0005),
+        Unit::Thousandths,
+    ),
+    range(
+        "master_volume",
+        "Master volume",
+        |s| s.master_volume,
+        |s, v| s.master_volume = v,
+        PERCENT,
+        Unit::Percent,
+    ),
+    range(
+        "music_volume",
+        "Music volume",
+        |s| s.music_volume,
+        |s, v| s.music_volume = v,
+        PERCENT,
+        Unit::Percent,
+    ),
+    range(
+        "sfx_volume",
+        "SFX volume",
+        |s| s.sfx_volume,
+        |s, v| s.sfx_volume = v,
+        PERCENT,
+        Unit::Percent,
+    ),
+    range(
+        "resolution_scale",
+        "Resolution scale",
+        |s| s.resolution_scale,
+        |s, v| s.resolution_scale = v,
+        (0.25, 1.0, 0.25),
+        Unit::Percent,
+    ),
+    Entry {
+        key: "upscale_filter",
+        name: "Upscale filter",
+        kind: Kind::Filter {
+            get: |s| s.upscale_filter,
+            set: |s, v| s.upscale_filter = v,
+        },
+    },
+    toggle(
+        "minimap_rotation",
+        "Rotating minimap",
+        |s| s.minimap_rotation,
+        |s, v| s.minimap_rotation = v,
+    ),
+    toggle(
+        "minimap_enemy",
+        "Enemy on minimap",
+        |s| s.minimap_enemy,
+        |s, v| s.minimap_enemy = v,
+    ),
+    toggle(
+        "scanlines",
+        "Scanlines",
+        |s| s.scanlines,
+        |s, v| s.scanlines = v,
+    ),
+    toggle(
+        "film_grain",
+        "Film grain",
+        |s| s.film_grain,
+        |s, v| s.film_grain = v,
+    ),
+    toggle(
+        "vignette",
+        "Vignette",
+        |s| s.vignette,
+        |s, v| s.vignette = v,
+    ),
+    toggle(
+        "chromatic_aberration",
+        "Chromatic aberration",
+        |s| s.chromatic_aberration,
+        |s, v| s.chromatic_aberration = v,
+    ),
+    toggle(
+        "color_grading",
+        "Color grading",
+        |s| s.color_grading,
+        |s, v| s.color_grading = v,
+    ),
+    toggle(
+        "timestamp",
+        "Timestamp",
+        |s| s.timestamp,
+        |s, v| s.timestamp = v,
+    ),
+    range(
+        "head_bob",
+        "Head bob",
+        |s| s.head_bob,
+        |s, v| s.head_bob = v,
+        (0.0, 1.0, 0.25),
+        Unit::Percent,
+    ),
+    range(
+        "screen_shake",
+        "Screen shake",
+        |s| s.screen_shake,
+        |s, v| s.screen_shake = v,
+        (0.0, 1.0, 0.25),
+        Unit::Percent,
+    ),
+];
+
+impl Entry {
+    // Valor actual tal como se guarda en `settings.cfg`
+    fn text(&self, settings: &Settings) -> String {
+        match self.kind {
+            Kind::Toggle { get, .. } => get(settings).to_string(),
+            Kind::Filter { get, .. } => get(settings).name().to_string(),
+            Kind::Range { get, .. } => get(settings).to_string(),
+        }
+    }
+
+    // Interpreta `text` y lo guarda; los valores mal formados se ignoran
+    fn parse(&self, settings: &mut Settings, text: &str) {
+        match self.kind {
+            Kind::Toggle { set, .. } => {
+                if let Some(on) = parse_toggle(text) {
+                    set(settings, on);
+                }
+            }
+            Kind::Filter { set, .. } => {
+                if let Some(filter) = parse_filter(text) {
+                    set(settings, filter);
+                }
+            }
+            Kind::Range { set, min, max, .. } => {
+                if let Ok(value) = text.parse::<f32>() {
+                    set(settings, value.clamp(min, max));
+                }
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Carga la configuración; si el archivo no existe se usan los valores por defecto.
+    /// Las claves desconocidas o mal formadas se ignoran y los números se
+    /// recortan a su rango.
+    pub fn load(path: &str) -> Settings {
+        let mut settings = Settings::default();
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return settings,
+        };
+
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let Some((key, text)) = line.split_once('=') else {
+                continue;
+            };
+            if let Some(entry) = ENTRIES.iter().find(|entry| entry.key == key.trim()) {
+                entry.parse(&mut settings, text.trim());
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for entry in &ENTRIES {
+            writeln!(writer, "{} = {}", entry.key, entry.text(self))?;
+        }
+        writer.flush()
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov_degrees.to_radians()
+    }
+
+    /// Efectos de posproceso encendidos.
+    pub fn effects(&self) -> PostEffects {
+        PostEffects {
+            scanlines: self.scanlines,
+            grain: self.film_grain,
+            vignette: self.vignette,
+            chromatic_aberration: self.chromatic_aberration,
+            color_grading: self.color_grading,
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Número de opciones que muestra la pantalla de configuración.
+    pub fn option_count() -> usize {
+        ENTRIES.len()
+    }
+
+    /// Texto de la opción `index` con su valor actual.
+    pub fn label(&self, index: usize) -> String {
+        let entry = &ENTRIES[index];
+        let value = match entry.kind {
+            Kind::Toggle { get, .. } => (if get(self) { "on" } else { "off" }).to_string(),
+            Kind::Filter { get, .. } => get(self).name().to_string(),
+            Kind::Range { get, unit, .. } => {
+                let value = get(self);
+                match unit {
+                    Unit::Degrees => format!("{:.0}", value),
+                    Unit::Thousandths => format!("{:.1}", value * 1000.0),
+                    Unit::Percent => format!("{:.0}%", value * 100.0),
+                }
+            }
+        };
+        format!("{}: {}", entry.name, value)
+    }
+
+    /// Sube (`direction > 0`) o baja (`direction < 0`) la opción `index` un paso,
+    /// manteniéndola dentro de su rango. Los interruptores y el filtro cambian
+    /// de valor con cualquier dirección.
+    pub fn adjust(&mut self, index: usize, direction: i32) {
+        match ENTRIES[index].kind {
+            Kind::Toggle { get, set } => set(self, !get(self)),
+            Kind::Filter { get, set } => {
+                let filter = match get(self) {
+                    Filter::Nearest => Filter::Bilinear,
+                    Filter::Bilinear => Filter::Nearest,
+                };
+                set(self, filter);
+            }
+            Kind::Range {
+                get,
+                set,
+                min,
+                max,
+                step,
+                ..
+            } => {
+                let steps = ((get(self) - min) / step).round() + direction as f32;
+                set(self, (min + steps * step).clamp(min, max));
+            }
+        }
+    }
+}
+
+// Los archivos de versiones anteriores guardan los interruptores y el filtro
+// como 0 o 1
+fn parse_toggle(text: &str) -> Option<bool> {
+    match text {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => text.parse::<f32>().ok().map(|value| value >= 0.5),
+    }
+}
+
+fn parse_filter(text: &str) -> Option<Filter> {
+    Filter::from_name(text).or_else(|| {
+        parse_toggle(text).map(|bilinear| {
+            if bilinear {
+                Filter::Bilinear
+            } else {
+                Filter::Nearest
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_settings_load_back() {
+        let mut settings = Settings::default();
+        settings.adjust(0, 2);
+        settings.adjust(6, 1);
+        settings.adjust(9, -1);
+        settings.adjust(16, -2);
+
+        let path = std::env::temp_dir().join("backrooms-settings-roundtrip.cfg");
+        let path = path.to_str().unwrap();
+        settings.save(path).unwrap();
+        let loaded = Settings::load(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, settings);
+        assert_eq!(Settings::load("no/such/settings.cfg"), Settings::default());
+    }
+
+    #[test]
+    fn loads_toggles_saved_as_numbers() {
+        let path = std::env::temp_dir().join("backrooms-settings-legacy.cfg");
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            "upscale_filter = 1\nscanlines = 0\nfov_degrees = 500\n",
+        )
+        .unwrap();
+        let loaded = Settings::load(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.upscale_filter, Filter::Bilinear);
+        assert!(!loaded.scanlines);
+        assert_eq!(loaded.fov_degrees, 100.0);
+        assert_eq!(loaded.label(9), "Scanlines: off");
+    }
+}
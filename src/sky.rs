@@ -0,0 +1,67 @@
+use crate::lighting::noise;
+use crate::texture::Texture;
+use once_cell::sync::Lazy;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+// Tamaño del panorama: el ancho da la vuelta completa alrededor del jugador y
+// el alto va del horizonte hasta arriba de la pantalla
+const WIDTH: usize = 2048;
+const HEIGHT: usize = 512;
+
+// Tamaño en píxeles de las celdas del ruido de las nubes, en dos escalas
+const CLOUD_CELL: [(usize, usize); 2] = [(128, 48), (32, 16)];
+
+// Colores del cielo arriba y en el horizonte y de las nubes: un cielo nublado
+// de atardecer
+const ZENITH: u32 = 0x1E2636;
+const HORIZON: u32 = 0xC0B48C;
+const CLOUD: u32 = 0x8C8478;
+
+static SKY: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(sky_texture()));
+
+/// Color del cielo mirando hacia `angle`, `from_horizon` filas por encima del
+/// horizonte. El panorama gira con el jugador y llega hasta `top` filas por
+/// encima del horizonte; más arriba se repite su primera fila.
+pub fn pixel(angle: f32, from_horizon: f32, top: f32) -> u32 {
+    let tx = (angle.rem_euclid(TAU) / TAU * WIDTH as f32) as usize;
+    let ty = ((1.0 - from_horizon / top) * HEIGHT as f32) as usize;
+    SKY.get_pixel(tx.min(WIDTH - 1), ty.min(HEIGHT - 1))
+}
+
+// Degradado del cenit al horizonte con nubes de ruido suave que se juntan
+// cerca del horizonte. El ruido se repite a lo ancho para que no haya costura
+// al dar la vuelta.
+fn sky_texture() -> Texture {
+    Texture::from_fn(WIDTH as u32, HEIGHT as u32, |x, y| {
+        let t = y as f32 / HEIGHT as f32;
+        let sky = mix(ZENITH, HORIZON, t * t);
+        let clouds =
+            smooth_noise(x, y, CLOUD_CELL[0], 0) + smooth_noise(x, y, CLOUD_CELL[1], 1) * 0.5;
+        let cover = ((clouds / 1.5 - 0.45) * 3.0).clamp(0.0, 1.0) * t.sqrt();
+        (mix(sky, CLOUD, cover), 255)
+    })
+}
+
+// Ruido de valor entre 0 y 1 interpolado entre los vértices de una grilla de
+// celdas de `cw` x `ch` píxeles
+fn smooth_noise(x: usize, y: usize, (cw, ch): (usize, usize), seed: u64) -> f32 {
+    let columns = WIDTH / cw;
+    let (gx, gy) = (x / cw, y / ch);
+    let (fx, fy) = ((x % cw) as f32 / cw as f32, (y % ch) as f32 / ch as f32);
+    let at = |gx: usize, gy: usize| noise((gy * columns + gx % columns) as u64 * 2 + seed);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (sx, sy) = (smooth(fx), smooth(fy));
+    let top = at(gx, gy) + (at(gx + 1, gy) - at(gx, gy)) * sx;
+    let bottom = at(gx, gy + 1) + (at(gx + 1, gy + 1) - at(gx, gy + 1)) * sx;
+    top + (bottom - top) * sy
+}
+
+fn mix(a: u32, b: u32, t: f32) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((a >> shift) & 0xFF) as f32;
+        let b = ((b >> shift) & 0xFF) as f32;
+        (a + (b - a) * t) as u32
+    };
+    (channel(16) << 16) | (channel(8) << 8) | channel(0)
+}
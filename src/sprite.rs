@@ -1,6 +1,9 @@
-use crate::color::Color;
+use crate::audio::Positional;
+use crate::cast_ray::cast_rays;
+use crate::doors::Doors;
 use crate::framebuffer::Framebuffer;
 use crate::input::is_collision;
+use crate::lighting::Lighting;
 use crate::player::Player;
 use crate::texture::Texture;
 
@@ -13,72 +16,123 @@ pub struct Sprite {
 }
 
 impl Sprite {
-    pub fn new(texture_path: &str, maze_x: f32, maze_y: f32, size: f32, speed: f32) -> Self {
-        let texture = Texture::new(texture_path);
-        Sprite {
+    pub fn new(
+        texture_path: &str,
+        maze_x: f32,
+        maze_y: f32,
+        size: f32,
+        speed: f32,
+    ) -> Result<Self, String> {
+        let texture = Texture::new(texture_path)?;
+        Ok(Sprite {
             texture,
             x: maze_x,
             y: maze_y,
             size,
             speed,
-        }
+        })
+    }
+
+    pub fn move_towards_player(&mut self, player: &Player, maze: &[Vec<char>], block_size: usize) {
+        (self.x, self.y) = step_towards(
+            (self.x, self.y),
+            (player.pos.x, player.pos.y),
+            self.speed,
+            maze,
+            block_size,
+        );
     }
 
-    pub fn move_towards_player(
-        &mut self,
+    /// Posiciones por las que pasará el sprite en los próximos `ticks` si el
+    /// jugador se queda quieto, una de cada `every` ticks.
+    pub fn predicted_path(
+        &self,
         player: &Player,
-        maze: &Vec<Vec<char>>,
+        maze: &[Vec<char>],
         block_size: usize,
-    ) {
-        let dx = player.pos.x - self.x;
-        let dy = player.pos.y - self.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-
-        if distance > 0.0 {
-            // Normalizar la dirección y aplicar la velocidad
-            let direction_x = dx / distance;
-            let direction_y = dy / distance;
+        ticks: usize,
+        every: usize,
+    ) -> Vec<(f32, f32)> {
+        let target = (player.pos.x, player.pos.y);
+        let mut position = (self.x, self.y);
+        let mut path = vec![position];
+        for tick in 1..=ticks {
+            position = step_towards(position, target, self.speed, maze, block_size);
+            if tick % every == 0 {
+                path.push(position);
+            }
+        }
+        path
+    }
 
-            let mut new_x = self.x + direction_x * self.speed;
-            let mut new_y = self.y + direction_y * self.speed;
+    /// Posición del sprite para el audio posicional. Un rayo desde el jugador
+    /// hacia el sprite indica si hay una pared de por medio.
+    pub fn audio_position(
+        &self,
+        player: &Player,
+        maze: &[Vec<char>],
+        doors: &Doors,
+        block_size: usize,
+    ) -> Positional {
+        Positional::new(
+            (player.pos.x, player.pos.y),
+            player.angle,
+            (self.x, self.y),
+            !self.sees_player(player, maze, doors, block_size),
+        )
+    }
 
-            // Verificar colisiones con el laberinto antes de mover el sprite
-            if is_collision(new_x, self.y, maze, block_size) {
-                // Intentar moverse perpendicularmente si hay colisión en la dirección X
-                new_x = self.x;
-                new_y += self.speed * if direction_y < 0.0 { -1.0 } else { 1.0 };
-            }
-            if is_collision(self.x, new_y, maze, block_size) {
-                // Intentar moverse perpendicularmente si hay colisión en la dirección Y
-                new_y = self.y;
-                new_x += self.speed * if direction_x < 0.0 { -1.0 } else { 1.0 };
-            }
+    /// Nivel de amenaza entre 0.0 y 1.0: crece al acercarse al jugador y
+    /// sube de golpe cuando no hay paredes entre ambos.
+    pub fn threat(
+        &self,
+        player: &Player,
+        maze: &[Vec<char>],
+        doors: &Doors,
+        block_size: usize,
+    ) -> f32 {
+        let dx = self.x - player.pos.x;
+        let dy = self.y - player.pos.y;
+        let distance = (dx * dx + dy * dy).sqrt();
 
-            // Si ambas direcciones están bloqueadas, retroceder
-            if is_collision(new_x, self.y, maze, block_size)
-                && is_collision(self.x, new_y, maze, block_size)
-            {
-                new_x = self.x - direction_x * self.speed;
-                new_y = self.y - direction_y * self.speed;
-            }
+        let proximity = (1.0 - distance / 6.0).clamp(0.0, 1.0);
+        let sight = if self.sees_player(player, maze, doors, block_size) {
+            0.4
+        } else {
+            0.0
+        };
+        (proximity * 0.6 + sight).min(1.0)
+    }
 
-            // Finalmente, aplicar el movimiento si no hay colisión
-            if !is_collision(new_x, self.y, maze, block_size) {
-                self.x = new_x;
-            }
+    /// Lanza un rayo desde el jugador hacia el sprite para ver si hay una pared de por medio.
+    pub fn sees_player(
+        &self,
+        player: &Player,
+        maze: &[Vec<char>],
+        doors: &Doors,
+        block_size: usize,
+    ) -> bool {
+        let dx = self.x - player.pos.x;
+        let dy = self.y - player.pos.y;
+        let distance = (dx * dx + dy * dy).sqrt();
 
-            if !is_collision(self.x, new_y, maze, block_size) {
-                self.y = new_y;
-            }
-        }
+        let hit = cast_rays(None, maze, doors, player, dy.atan2(dx), block_size);
+        hit.distance >= distance * block_size as f32
     }
 
-    pub fn render(&self, framebuffer: &mut Framebuffer, player: &Player) {
+    /// Dibuja el sprite centrado en la fila `horizon`.
+    pub fn render(
+        &self,
+        framebuffer: &mut Framebuffer,
+        player: &Player,
+        lighting: &Lighting,
+        horizon: f32,
+    ) {
         let dx = self.x - player.pos.x;
         let dy = self.y - player.pos.y;
         let distance = (dx * dx + dy * dy).sqrt();
 
-        if distance < 0.5 || distance > 50.0 {
+        if !(0.5..=50.0).contains(&distance) {
             // No renderizar si el sprite está demasiado cerca o demasiado lejos
             return;
         }
@@ -105,8 +159,7 @@ impl Sprite {
         let start_x = (screen_x as isize - sprite_width as isize / 2).max(0) as usize;
         let end_x = (start_x + sprite_width).min(framebuffer.width);
 
-        let start_y = ((framebuffer.height as isize / 2 - sprite_height as isize / 2).max(0)
-            as usize)
+        let start_y = ((horizon as isize - sprite_height as isize / 2).max(0) as usize)
             .min(framebuffer.height);
         let end_y = (start_y + sprite_height).min(framebuffer.height);
 
@@ -115,12 +168,68 @@ impl Sprite {
             for x in start_x..end_x {
                 let tx = (x - start_x) * self.texture.width as usize / sprite_width;
                 let ty = (y - start_y) * self.texture.height as usize / sprite_height;
-                let color = self.texture.get_pixel(tx, ty);
-                if color != Color::new(0, 0, 0) {
+                let pixel = self.texture.get_pixel(tx, ty);
+                if pixel != 0 {
                     // No dibujar píxeles transparentes (negros)
-                    framebuffer.point_with_color(x, y, color);
+                    let point = (self.x, self.y);
+                    framebuffer.point(x, y, lighting.shade(pixel, point, distance, x, y));
                 }
             }
         }
     }
 }
+
+// Un paso del sprite hacia `target`, deslizándose por las paredes
+fn step_towards(
+    (x, y): (f32, f32),
+    target: (f32, f32),
+    speed: f32,
+    maze: &[Vec<char>],
+    block_size: usize,
+) -> (f32, f32) {
+    let dx = target.0 - x;
+    let dy = target.1 - y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    if distance <= 0.0 {
+        return (x, y);
+    }
+
+    // Normalizar la dirección y aplicar la velocidad
+    let direction_x = dx / distance;
+    let direction_y = dy / distance;
+
+    let mut new_x = x + direction_x * speed;
+    let mut new_y = y + direction_y * speed;
+
+    // Verificar colisiones con el laberinto antes de mover el sprite
+    if is_collision(new_x, y, maze, block_size) {
+        // Intentar moverse perpendicularmente si hay colisión en la dirección X
+        new_x = x;
+        new_y += speed * if direction_y < 0.0 { -1.0 } else { 1.0 };
+    }
+    if is_collision(x, new_y, maze, block_size) {
+        // Intentar moverse perpendicularmente si hay colisión en la dirección Y
+        new_y = y;
+        new_x += speed * if direction_x < 0.0 { -1.0 } else { 1.0 };
+    }
+
+    // Si ambas direcciones están bloqueadas, retroceder
+    if is_collision(new_x, y, maze, block_size) && is_collision(x, new_y, maze, block_size) {
+        new_x = x - direction_x * speed;
+        new_y = y - direction_y * speed;
+    }
+
+    // Finalmente, aplicar el movimiento si no hay colisión
+    let x = if !is_collision(new_x, y, maze, block_size) {
+        new_x
+    } else {
+        x
+    };
+    let y = if !is_collision(x, new_y, maze, block_size) {
+        new_y
+    } else {
+        y
+    };
+    (x, y)
+}
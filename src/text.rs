@@ -0,0 +1,123 @@
+use crate::color::Color;
+use once_cell::sync::Lazy;
+use rusttype::{point, Font, PositionedGlyph, Scale};
+
+// La fuente se interpreta una sola vez y se comparte entre todos los textos
+static FONT: Lazy<Font<'static>> = Lazy::new(|| {
+    Font::try_from_bytes(include_bytes!("../SIXTY.TTF") as &[u8]).expect("Invalid SIXTY.TTF")
+});
+
+/// Alineación horizontal respecto a la `x` con la que se dibuja el texto.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Cómo se dibuja un texto: tamaño, color, alineación, sombra y ancho máximo
+/// antes de partir las líneas.
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    pub size: f32,
+    pub color: Color,
+    pub align: Align,
+    pub shadow: Option<Color>,
+    pub max_width: Option<usize>,
+}
+
+impl TextStyle {
+    pub fn new(size: f32, color: Color) -> Self {
+        TextStyle {
+            size,
+            color,
+            align: Align::Left,
+            shadow: None,
+            max_width: None,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn shadow(mut self, color: Color) -> Self {
+        self.shadow = Some(color);
+        self
+    }
+
+    pub fn wrap(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Distancia vertical entre el inicio de una línea y el de la siguiente.
+    pub fn line_height(&self) -> usize {
+        let metrics = FONT.v_metrics(Scale::uniform(self.size));
+        (metrics.ascent - metrics.descent + metrics.line_gap).ceil() as usize
+    }
+
+    /// Desplazamiento de la sombra respecto al texto, en ambos ejes.
+    pub fn shadow_offset(&self) -> i32 {
+        (self.size / 16.0).round().max(1.0) as i32
+    }
+}
+
+/// Glifos de una línea con el origen en la esquina superior izquierda.
+pub fn layout_line(text: &str, size: f32) -> Vec<PositionedGlyph<'static>> {
+    let scale = Scale::uniform(size);
+    let ascent = FONT.v_metrics(scale).ascent;
+    FONT.layout(text, scale, point(0.0, ascent)).collect()
+}
+
+/// Ancho en píxeles de una sola línea.
+pub fn line_width(text: &str, size: f32) -> usize {
+    layout_line(text, size)
+        .last()
+        .map(|glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width)
+        .unwrap_or(0.0)
+        .ceil() as usize
+}
+
+/// Parte el texto en líneas: en cada `\n` y, si el estilo tiene ancho
+/// máximo, entre palabras para no pasarse de él. Una palabra más larga que
+/// el ancho queda sola en su línea.
+pub fn wrap_lines(text: &str, style: &TextStyle) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let Some(max_width) = style.max_width else {
+            lines.push(paragraph.to_string());
+            continue;
+        };
+
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if line.is_empty() || line_width(&candidate, style.size) <= max_width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Ancho y alto que ocupa el texto con el estilo dado.
+pub fn measure_text(text: &str, style: &TextStyle) -> (usize, usize) {
+    let lines = wrap_lines(text, style);
+    let width = lines
+        .iter()
+        .map(|line| line_width(line, style.size))
+        .max()
+        .unwrap_or(0);
+    (width, lines.len() * style.line_height())
+}
@@ -1,33 +1,62 @@
 extern crate image;
 
-use crate::color::Color;
-use image::{DynamicImage, GenericImageView};
-use image::{ImageReader, RgbImage};
+use image::ImageReader;
 
 pub struct Texture {
-    image: RgbImage,
+    // Píxeles `0RGB` por filas, el mismo formato que el framebuffer
+    pixels: Vec<u32>,
+    // Opacidad de cada píxel (255 es opaco)
+    alpha: Vec<u8>,
     pub width: u32,
     pub height: u32,
 }
 
 impl Texture {
-    pub fn new(file_path: &str) -> Texture {
+    /// Carga una imagen; falla si no se puede abrir o decodificar.
+    pub fn new(file_path: &str) -> Result<Texture, String> {
         let img = ImageReader::open(file_path)
-            .unwrap()
+            .map_err(|e| format!("Can't open texture {}: {}", file_path, e))?
             .decode()
-            .unwrap()
-            .to_rgb8();
+            .map_err(|e| format!("Can't decode texture {}: {}", file_path, e))?
+            .to_rgba8();
         let width = img.width();
         let height = img.height();
+        let pixels = img
+            .pixels()
+            .map(|p| ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32)
+            .collect();
+        let alpha = img.pixels().map(|p| p[3]).collect();
+        Ok(Texture {
+            pixels,
+            alpha,
+            width,
+            height,
+        })
+    }
+
+    /// Textura generada: `f` da el color `0RGB` y la opacidad de cada píxel.
+    pub fn from_fn(width: u32, height: u32, f: impl Fn(usize, usize) -> (u32, u8)) -> Texture {
+        let (pixels, alpha) = (0..height as usize)
+            .flat_map(|y| (0..width as usize).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .unzip();
         Texture {
-            image: img,
+            pixels,
+            alpha,
             width,
             height,
         }
     }
 
-    pub fn get_pixel(&self, x: usize, y: usize) -> Color {
-        let pixel = self.image.get_pixel(x as u32, y as u32);
-        Color::new(pixel[0] as i32, pixel[1] as i32, pixel[2] as i32)
+    /// Píxel en formato `0RGB`, listo para copiarse al framebuffer.
+    pub fn get_pixel(&self, x: usize, y: usize) -> u32 {
+        assert!(x < self.width as usize && y < self.height as usize);
+        self.pixels[y * self.width as usize + x]
+    }
+
+    /// Opacidad del píxel, de 0 (transparente) a 255 (opaco).
+    pub fn get_alpha(&self, x: usize, y: usize) -> u8 {
+        assert!(x < self.width as usize && y < self.height as usize);
+        self.alpha[y * self.width as usize + x]
     }
 }
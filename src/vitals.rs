@@ -0,0 +1,136 @@
+use crate::input::is_collision;
+use crate::items::ItemKind;
+use crate::player::Player;
+use crate::sprite::Sprite;
+
+pub const MAX_HEALTH: f32 = 100.0;
+pub const MAX_SANITY: f32 = 100.0;
+
+// Distancia (en celdas) a la que el monstruo alcanza al jugador
+const CONTACT_DISTANCE: f32 = 0.5;
+
+// Daño por contacto, ticks de invulnerabilidad después y cuánto retrocede el jugador
+const CONTACT_DAMAGE: f32 = 35.0;
+pub const INVULNERABLE_TICKS: u32 = 90;
+const KNOCKBACK: f32 = 0.8;
+const KNOCKBACK_STEP: f32 = 0.05;
+
+// Cordura que se pierde por tick con el monstruo encima (amenaza 1.0) y a
+// oscuras del todo, y la que se recupera en calma
+const THREAT_DRAIN: f32 = 0.08;
+const DARKNESS_DRAIN: f32 = 0.03;
+const SANITY_REGEN: f32 = 0.005;
+
+// Sin cordura se pierde salud poco a poco
+const INSANITY_DAMAGE: f32 = 0.05;
+
+// Lo que recupera una botella de agua de almendras
+const ALMOND_WATER_SANITY: f32 = 35.0;
+const ALMOND_WATER_HEALTH: f32 = 10.0;
+
+/// Contacto con el monstruo: quita salud, empuja al jugador en dirección
+/// contraria (sin atravesar paredes) y lo vuelve invulnerable un momento.
+pub fn contact(player: &mut Player, sprite: &Sprite, maze: &[Vec<char>], block_size: usize) {
+    player.invulnerable = player.invulnerable.saturating_sub(1);
+
+    let dx = player.pos.x - sprite.x;
+    let dy = player.pos.y - sprite.y;
+    let distance = dx.hypot(dy);
+    if distance >= CONTACT_DISTANCE || player.invulnerable > 0 {
+        return;
+    }
+
+    player.health = (player.health - CONTACT_DAMAGE).max(0.0);
+    player.invulnerable = INVULNERABLE_TICKS;
+
+    // Si están en el mismo punto se empuja hacia atrás de donde mira
+    let (nx, ny) = if distance > 0.0 {
+        (dx / distance, dy / distance)
+    } else {
+        (-player.dir.x, -player.dir.y)
+    };
+    let mut pushed = 0.0;
+    while pushed < KNOCKBACK {
+        let x = player.pos.x + nx * KNOCKBACK_STEP;
+        let y = player.pos.y + ny * KNOCKBACK_STEP;
+        if is_collision(x, y, maze, block_size) {
+            break;
+        }
+        player.pos.x = x;
+        player.pos.y = y;
+        pushed += KNOCKBACK_STEP;
+    }
+}
+
+/// Cordura de un tick: baja con la amenaza del monstruo (0 a 1) y la
+/// oscuridad (0 iluminado, 1 a oscuras) y sube despacio en calma. Sin cordura
+/// se pierde salud.
+pub fn update_sanity(player: &mut Player, threat: f32, darkness: f32) {
+    let drain = threat * THREAT_DRAIN + darkness * DARKNESS_DRAIN;
+    player.sanity = if drain > 0.0 {
+        (player.sanity - drain).max(0.0)
+    } else {
+        (player.sanity + SANITY_REGEN).min(MAX_SANITY)
+    };
+
+    if player.sanity <= 0.0 {
+        player.health = (player.health - INSANITY_DAMAGE).max(0.0);
+    }
+}
+
+/// Usa un objeto del inventario y devuelve el mensaje para el HUD: el agua de
+/// almendras devuelve cordura y algo de salud, la batería recarga la linterna.
+/// Los objetos que no se pueden usar se quedan en el inventario.
+pub fn use_item(player: &mut Player, kind: ItemKind) -> String {
+    if !kind.is_usable() {
+        return kind.use_message().to_string();
+    }
+    if !player.inventory.take(kind) {
+        return format!("No {} left", kind.name().to_lowercase());
+    }
+    match kind {
+        ItemKind::AlmondWater => {
+            player.sanity = (player.sanity + ALMOND_WATER_SANITY).min(MAX_SANITY);
+            player.health = (player.health + ALMOND_WATER_HEALTH).min(MAX_HEALTH);
+        }
+        ItemKind::Battery => player.flashlight.battery = 1.0,
+        ItemKind::Key | ItemKind::Note => {}
+    }
+    kind.use_message().to_string()
+}
+
+pub fn is_dead(player: &Player) -> bool {
+    player.health <= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn using_a_key_keeps_it() {
+        let mut player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+        player.inventory.add(ItemKind::Key);
+
+        assert_eq!(
+            use_item(&mut player, ItemKind::Key),
+            "You can't use that here"
+        );
+        assert_eq!(player.inventory.count(ItemKind::Key), 1);
+    }
+
+    #[test]
+    fn using_almond_water_spends_it() {
+        let mut player = Player::new(1.5, 1.5, 1.0, 0.02, 0.1);
+        player.sanity = 10.0;
+        player.inventory.add(ItemKind::AlmondWater);
+
+        use_item(&mut player, ItemKind::AlmondWater);
+        assert_eq!(player.inventory.count(ItemKind::AlmondWater), 0);
+        assert!(player.sanity > 10.0);
+        assert_eq!(
+            use_item(&mut player, ItemKind::AlmondWater),
+            "No almond water left"
+        );
+    }
+}